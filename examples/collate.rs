@@ -1,6 +1,6 @@
 use std::error::Error;
 use std::fs::File;
-use std::io::Write;
+use std::io::BufWriter;
 
 use rust_texas::prelude::*;
 
@@ -13,11 +13,12 @@ use walkdir::WalkDir;
 /// Ideally, you'd be doing this for a bunch of markdown files or text files, but
 /// it seemed easier to demonstrate this way. That said, it's still a somewhat complex example.
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut q = File::create("examples/tex/source_code.tex")?;
+    let mut q = BufWriter::new(File::create("examples/tex/source_code.tex")?);
     let mut doc = document!("amsart");
     doc.disable_hyperref();
 
     // Using the listings package, because we're making a source code pdf.
+    // The listings would pull it in anyway, and adding a package twice is harmless.
     doc.new_package(Package::new("listings"));
 
     // The final, NEW, function from the Populate trait is attach_iter. Here, we feed it an iterator over
@@ -32,23 +33,19 @@ fn main() -> Result<(), Box<dyn Error>> {
             let fname = x.into_path();
             let fname = fname.to_str().unwrap();
 
-            // This reads the contents of a file and dumps it (raw) into a TextChunk component with TextType::Normal.
-            // At this stage, it's just a string.
-            let buf = TextChunk::from_file(&fname).ok()?;
-
-            // Because this is source code, we'll put it in an lstlisting environment.
-            let mut vb = Environment::new("lstlisting");
-            // And embed buf in the lstlisting.
-            vb.attach(Component::TextChunk(buf)).ok()?;
+            // This reads the contents of a file into a listing, guessing the language from the extension.
+            let mut listing = CodeListing::from_file(fname).ok()?;
+            listing.set_line_numbers(true);
 
             // We'll create a new section for each file, for no particular reason.
             let mut sec = section!(&fname);
-            sec.attach(Component::Environment(vb)).ok()?;
+            sec.attach(listing.into()).ok()?;
             Some(sec)
         }
     }))?;
 
-    writeln!(q, "{}", doc.to_string())?;
+    // Big documents are better streamed straight into the file than built up as one String.
+    doc.write_to(&mut q)?;
 
     Ok(())
 }
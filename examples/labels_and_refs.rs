@@ -15,10 +15,18 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let f2 = figure!("ss.png");
     let lb2 = label!("fig:f2");
-    
-    let l1 = Line::with_components(vec![textchunk!("This is a reference: "), reference!("fig:f1")]);
+
+    let l1 = Line::with_components(vec![
+        textchunk!("This is a reference: "),
+        reference!("fig:f1"),
+    ]);
     let l2 = Line::with_components(vec![textchunk!("And so is this: "), reference!("fig:f2")]);
-    doc.attach(f1)?.attach(lb1)?.attach(f2)?.attach(lb2)?.attach(Component::Line(l1))?.attach(Component::Line(l2))?;
+    doc.attach(f1)?
+        .attach(lb1)?
+        .attach(f2)?
+        .attach(lb2)?
+        .attach(Component::Line(l1))?
+        .attach(Component::Line(l2))?;
 
     writeln!(q, "{}", doc.to_string())?;
 
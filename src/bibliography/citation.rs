@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// `\parencite` and `\textcite` are biblatex commands. With any other backend, the document
+/// falls back to `\cite` for them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiteKind {
+    Cite,
+    Parencite,
+    Textcite,
+}
+impl CiteKind {
+    pub fn command(&self) -> &str {
+        match &self {
+            CiteKind::Cite => "cite",
+            CiteKind::Parencite => "parencite",
+            CiteKind::Textcite => "textcite",
+        }
+    }
+}
+/// Fails on anything it doesn't know, rather than quietly making it a `\cite`.
+impl TryFrom<&str> for CiteKind {
+    type Error = TexError;
+
+    fn try_from(value: &str) -> TexResult<Self> {
+        match value {
+            "cite" => Ok(Self::Cite),
+            "parencite" | "paren" => Ok(Self::Parencite),
+            "textcite" | "text" => Ok(Self::Textcite),
+            _ => Err(TexError::VariantUndefined),
+        }
+    }
+}
+
+/// `\cite{a,b}`, optionally with a note (`\cite[p.~4]{a}`). Has macro support.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    pub(crate) kind: CiteKind,
+    pub(crate) keys: Vec<String>,
+    note: Option<String>,
+}
+impl AsLatex for Citation {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let note = self
+            .note
+            .as_ref()
+            .map(|x| format!("[{}]", x))
+            .unwrap_or_default();
+        let sep = if self.kind == CiteKind::Textcite {
+            " "
+        } else {
+            "~"
+        };
+        writeln!(
+            w,
+            "{}\\{}{}{{{}}} ",
+            sep,
+            self.kind.command(),
+            note,
+            self.keys.join(",")
+        )
+    }
+}
+impl Citation {
+    pub fn new(kind: CiteKind, keys: &[&str]) -> Self {
+        Self {
+            kind,
+            keys: keys.iter().map(|x| x.to_string()).collect(),
+            note: None,
+        }
+    }
+
+    pub fn set_note(&mut self, note: &str) {
+        self.note = Some(note.to_string());
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    pub fn kind(&self) -> &CiteKind {
+        &self.kind
+    }
+}
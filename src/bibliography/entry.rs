@@ -0,0 +1,227 @@
+use std::io::{self, Write};
+
+/// The `@type` of a bibliography entry. Anything not listed here ends up in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EntryType {
+    Article,
+    Book,
+    Booklet,
+    InBook,
+    InCollection,
+    InProceedings,
+    Manual,
+    MastersThesis,
+    PhdThesis,
+    Proceedings,
+    TechReport,
+    Unpublished,
+    Online,
+    Misc,
+    Other(String),
+}
+impl EntryType {
+    /// As written after the `@`.
+    pub fn name(&self) -> &str {
+        match &self {
+            EntryType::Article => "article",
+            EntryType::Book => "book",
+            EntryType::Booklet => "booklet",
+            EntryType::InBook => "inbook",
+            EntryType::InCollection => "incollection",
+            EntryType::InProceedings => "inproceedings",
+            EntryType::Manual => "manual",
+            EntryType::MastersThesis => "mastersthesis",
+            EntryType::PhdThesis => "phdthesis",
+            EntryType::Proceedings => "proceedings",
+            EntryType::TechReport => "techreport",
+            EntryType::Unpublished => "unpublished",
+            EntryType::Online => "online",
+            EntryType::Misc => "misc",
+            EntryType::Other(s) => s,
+        }
+    }
+
+    /// Whether the title is the thing to italicise, as opposed to the journal/book it appeared in.
+    fn standalone(&self) -> bool {
+        matches!(
+            self,
+            EntryType::Book
+                | EntryType::Booklet
+                | EntryType::Manual
+                | EntryType::MastersThesis
+                | EntryType::PhdThesis
+                | EntryType::Proceedings
+                | EntryType::TechReport
+        )
+    }
+}
+impl From<&str> for EntryType {
+    fn from(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "article" => Self::Article,
+            "book" => Self::Book,
+            "booklet" => Self::Booklet,
+            "inbook" => Self::InBook,
+            "incollection" => Self::InCollection,
+            "inproceedings" | "conference" => Self::InProceedings,
+            "manual" => Self::Manual,
+            "mastersthesis" => Self::MastersThesis,
+            "phdthesis" => Self::PhdThesis,
+            "proceedings" => Self::Proceedings,
+            "techreport" => Self::TechReport,
+            "unpublished" => Self::Unpublished,
+            "online" | "electronic" | "www" => Self::Online,
+            "misc" => Self::Misc,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+/// One entry of a bibliography. Field values are latex, and are written out as-is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BibEntry {
+    pub(crate) typ: EntryType,
+    pub(crate) key: String,
+    /// Field names are lowercase. Kept in insertion order.
+    pub(crate) fields: Vec<(String, String)>,
+}
+impl BibEntry {
+    pub fn new(typ: EntryType, key: &str) -> Self {
+        Self {
+            typ,
+            key: key.to_string(),
+            fields: vec![],
+        }
+    }
+
+    pub fn article(key: &str, author: &[&str], title: &str, journal: &str, year: &str) -> Self {
+        let mut out = Self::new(EntryType::Article, key);
+        out.set_author(author)
+            .set("title", title)
+            .set("journal", journal)
+            .set("year", year);
+        out
+    }
+
+    pub fn book(key: &str, author: &[&str], title: &str, publisher: &str, year: &str) -> Self {
+        let mut out = Self::new(EntryType::Book, key);
+        out.set_author(author)
+            .set("title", title)
+            .set("publisher", publisher)
+            .set("year", year);
+        out
+    }
+
+    pub fn inproceedings(
+        key: &str,
+        author: &[&str],
+        title: &str,
+        booktitle: &str,
+        year: &str,
+    ) -> Self {
+        let mut out = Self::new(EntryType::InProceedings, key);
+        out.set_author(author)
+            .set("title", title)
+            .set("booktitle", booktitle)
+            .set("year", year);
+        out
+    }
+
+    pub fn misc(key: &str, author: &[&str], title: &str) -> Self {
+        let mut out = Self::new(EntryType::Misc, key);
+        out.set_author(author).set("title", title);
+        out
+    }
+
+    /// Replaces the field if it is already there.
+    pub fn set(&mut self, field: &str, value: &str) -> &mut Self {
+        let field = field.to_lowercase();
+        match self.fields.iter_mut().find(|x| x.0 == field) {
+            Some(f) => f.1 = value.to_string(),
+            None => self.fields.push((field, value.to_string())),
+        }
+        self
+    }
+
+    /// Joins the names with ` and `, the way bibtex wants them.
+    pub fn set_author(&mut self, author: &[&str]) -> &mut Self {
+        self.set("author", &author.join(" and "))
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        let field = field.to_lowercase();
+        self.fields
+            .iter()
+            .find(|x| x.0 == field)
+            .map(|x| x.1.as_str())
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn typ(&self) -> &EntryType {
+        &self.typ
+    }
+
+    /// The entry as it would appear in a `.bib` file.
+    pub fn write_bib(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "@{}{{{},", self.typ.name(), self.key)?;
+        for (name, value) in &self.fields {
+            writeln!(w, "  {} = {{{}}},", name, value)?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// The entry as a `\bibitem`, for `thebibliography`. Roughly what the `plain` style does.
+    pub fn write_bibitem(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\bibitem{{{}}} ", self.key)?;
+        if let Some(author) = self.get("author").or(self.get("editor")) {
+            write!(
+                w,
+                "{}. ",
+                author.split(" and ").collect::<Vec<_>>().join(", ")
+            )?;
+        }
+        let container = [
+            "journal",
+            "booktitle",
+            "publisher",
+            "school",
+            "institution",
+            "howpublished",
+        ]
+        .iter()
+        .find_map(|x| self.get(x));
+        if let Some(title) = self.get("title") {
+            if self.typ.standalone() {
+                write!(w, "\\newblock \\emph{{{}}}. ", title)?;
+            } else {
+                write!(w, "\\newblock {}. ", title)?;
+            }
+        }
+
+        let mut rest = vec![];
+        match container {
+            Some(c) if !self.typ.standalone() => rest.push(format!("\\emph{{{}}}", c)),
+            Some(c) => rest.push(c.to_string()),
+            None => {}
+        }
+        if let Some(volume) = self.get("volume") {
+            rest.push(match self.get("number") {
+                Some(number) => format!("{}({})", volume, number),
+                None => volume.to_string(),
+            });
+        }
+        if let Some(pages) = self.get("pages") {
+            rest.push(format!("pages {}", pages));
+        }
+        if let Some(year) = self.get("year") {
+            rest.push(year.to_string());
+        }
+        if !rest.is_empty() {
+            write!(w, "\\newblock {}.", rest.join(", "))?;
+        }
+        writeln!(w)
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::prelude::*;
+
+mod citation;
+mod entry;
+mod parse;
+
+pub use citation::*;
+pub use entry::*;
+pub use parse::*;
+
+/// How the bibliography ends up in the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BibBackend {
+    /// A `thebibliography` environment at the end of the document. Needs nothing else to compile.
+    Inline,
+    /// `<file>.bib`, with `\bibliographystyle{<style>}` and `\bibliography{<file>}`. Run `bibtex`.
+    Bibtex { file: String, style: String },
+    /// `<file>.bib`, with biblatex loaded in `<style>`, `\addbibresource` and `\printbibliography`.
+    /// Run `biber`.
+    Biblatex { file: String, style: String },
+}
+
+/// Everything that can be cited in a `Document`, and how to print it.
+///
+/// ```rust
+/// use rust_texas::prelude::*;
+/// fn dummy() -> TexResult<()> {
+///     let mut doc = document!("article");
+///     let mut bib = Bibliography::new(BibBackend::Inline);
+///     bib.add_entry(BibEntry::book("knuth84", &["Donald E. Knuth"], "The \\TeX book", "Addison-Wesley", "1984"));
+///     doc.set_bibliography(bib);
+///     doc.attach(cite!("knuth84"))?;
+///     doc.validate()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Bibliography {
+    backend: BibBackend,
+    entries: Vec<BibEntry>,
+    show_all: bool,
+}
+impl Bibliography {
+    pub fn new(backend: BibBackend) -> Self {
+        Self {
+            backend,
+            entries: vec![],
+            show_all: false,
+        }
+    }
+
+    /// Entries straight out of the contents of a `.bib` file.
+    pub fn from_bib(backend: BibBackend, src: &str) -> TexResult<Self> {
+        let mut out = Self::new(backend);
+        out.entries = parse_bib(src)?;
+        Ok(out)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(backend: BibBackend, path: P) -> TexResult<Self> {
+        Self::from_bib(backend, &fs::read_to_string(path)?)
+    }
+
+    pub fn add_entry(&mut self, entry: BibEntry) -> &mut Self {
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn entries(&self) -> &[BibEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BibEntry> {
+        self.entries.iter().find(|x| x.key == key)
+    }
+
+    pub fn backend(&self) -> &BibBackend {
+        &self.backend
+    }
+
+    /// Print every entry, not just the ones that are cited (`\nocite{*}`).
+    pub fn show_all(&mut self) -> &mut Self {
+        self.show_all = true;
+        self
+    }
+
+    /// Name of the `.bib` file the document expects, if the backend needs one.
+    pub fn file_name(&self) -> Option<String> {
+        match &self.backend {
+            BibBackend::Inline => None,
+            BibBackend::Bibtex { file, .. } | BibBackend::Biblatex { file, .. } => {
+                Some(format!("{}.bib", file))
+            }
+        }
+    }
+
+    /// The program that has to run between engine passes, if any.
+    pub fn tool(&self) -> Option<&str> {
+        match &self.backend {
+            BibBackend::Inline => None,
+            BibBackend::Bibtex { .. } => Some("bibtex"),
+            BibBackend::Biblatex { .. } => Some("biber"),
+        }
+    }
+
+    /// The contents of the `.bib` file.
+    pub fn write_bib(&self, w: &mut dyn Write) -> io::Result<()> {
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                writeln!(w)?;
+            }
+            e.write_bib(w)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the `.bib` file into `dir`, if the backend needs one.
+    pub fn write_file<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        if let Some(name) = self.file_name() {
+            let mut f = io::BufWriter::new(fs::File::create(dir.as_ref().join(name))?);
+            self.write_bib(&mut f)?;
+            f.flush()?;
+        }
+        Ok(())
+    }
+
+    /// biblatex, for the biblatex backend.
+    pub fn packages(&self) -> Vec<Package> {
+        match &self.backend {
+            BibBackend::Biblatex { style, .. } => {
+                vec![Package::with_options(
+                    "biblatex",
+                    &[&format!("style={}", style)],
+                )]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// What goes into the preamble, after the packages. `fallbacks` is set when the document
+    /// uses biblatex-only citation commands.
+    pub(crate) fn write_preamble(&self, w: &mut dyn Write, fallbacks: bool) -> io::Result<()> {
+        match &self.backend {
+            BibBackend::Biblatex { file, .. } => {
+                writeln!(w, "\\addbibresource{{{}.bib}}", file)?;
+            }
+            _ if fallbacks => {
+                writeln!(w, "\\providecommand{{\\parencite}}{{\\cite}}")?;
+                writeln!(w, "\\providecommand{{\\textcite}}{{\\cite}}")?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// What goes at the end of the document. `cited` is every key cited, in order.
+    pub(crate) fn write_body(&self, w: &mut dyn Write, cited: &[&str]) -> io::Result<()> {
+        writeln!(w)?;
+        if self.show_all && self.backend != BibBackend::Inline {
+            writeln!(w, "\\nocite{{*}}")?;
+        }
+        match &self.backend {
+            BibBackend::Inline => {
+                let entries = self
+                    .entries
+                    .iter()
+                    .filter(|x| self.show_all || cited.contains(&x.key.as_str()))
+                    .collect::<Vec<_>>();
+                // LaTeX won't have a thebibliography without any \bibitem in it.
+                if entries.is_empty() {
+                    return Ok(());
+                }
+                writeln!(
+                    w,
+                    "\\begin{{thebibliography}}{{{}}}",
+                    "9".repeat(entries.len().to_string().len())
+                )?;
+                for e in entries {
+                    e.write_bibitem(w)?;
+                }
+                writeln!(w, "\\end{{thebibliography}}")
+            }
+            BibBackend::Bibtex { file, style } => {
+                writeln!(w, "\\bibliographystyle{{{}}}", style)?;
+                writeln!(w, "\\bibliography{{{}}}", file)
+            }
+            BibBackend::Biblatex { .. } => writeln!(w, "\\printbibliography"),
+        }
+    }
+}
+
+/// Cross-checks citations against the bibliography: citations of keys that aren't in it (or of
+/// anything at all, when there is no bibliography), and keys defined twice.
+pub fn check_citations(bib: Option<&Bibliography>, citations: &[&Citation]) -> Vec<TexError> {
+    let mut out = vec![];
+
+    let mut keys = HashSet::new();
+    for e in bib.map(|x| x.entries()).unwrap_or_default() {
+        if !keys.insert(e.key()) {
+            out.push(TexError::CitationDuplicate(e.key().to_string()));
+        }
+    }
+
+    let mut reported = HashSet::new();
+    for key in citations.iter().flat_map(|x| x.keys()) {
+        if !keys.contains(key.as_str()) && reported.insert(key) {
+            out.push(TexError::CitationUndefined(key.clone()));
+        }
+    }
+
+    out
+}
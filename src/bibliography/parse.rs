@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use crate::prelude::*;
+
+/// Reads the entries out of a `.bib` file. `@string` macros (and the predefined month
+/// abbreviations) are expanded, `#` concatenation is resolved, and `@comment`/`@preamble` are
+/// skipped. Values keep whatever latex they contain.
+pub fn parse_bib(src: &str) -> TexResult<Vec<BibEntry>> {
+    let mut p = Parser {
+        src: src.chars().collect(),
+        pos: 0,
+        strings: MONTHS
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    };
+    let mut out = vec![];
+
+    while p.skip_to('@') {
+        p.pos += 1;
+        let typ = p.ident()?.to_lowercase();
+        p.skip_ws();
+        let close = match p.next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return Err(p.error(&format!("expected {{ after @{}", typ))),
+        };
+        match typ.as_str() {
+            "comment" | "preamble" => p.skip_group(close)?,
+            "string" => {
+                let (name, value) = p.field()?;
+                p.strings.insert(name, value);
+                p.skip_ws();
+                p.expect(close)?;
+            }
+            _ => out.push(p.entry(&typ, close)?),
+        }
+    }
+
+    Ok(out)
+}
+
+const MONTHS: [(&str, &str); 12] = [
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+struct Parser {
+    src: Vec<char>,
+    pos: usize,
+    strings: HashMap<String, String>,
+}
+impl Parser {
+    fn error(&self, msg: &str) -> TexError {
+        let line = self.src[..self.pos.min(self.src.len())]
+            .iter()
+            .filter(|&&x| x == '\n')
+            .count()
+            + 1;
+        TexError::BibError(format!("line {}: {}", line, msg))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    fn expect(&mut self, c: char) -> TexResult<()> {
+        match self.next() {
+            Some(x) if x == c => Ok(()),
+            _ => {
+                self.pos -= 1;
+                Err(self.error(&format!("expected {}", c)))
+            }
+        }
+    }
+
+    /// Anything outside an entry is a comment, as far as bibtex is concerned.
+    fn skip_to(&mut self, c: char) -> bool {
+        while let Some(x) = self.peek() {
+            if x == c {
+                return true;
+            }
+            self.pos += 1;
+        }
+        false
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|x| x.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn ident(&mut self) -> TexResult<String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|x| !x.is_whitespace() && !"{}()=,#\"".contains(x))
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a name"));
+        }
+        Ok(self.src[start..self.pos].iter().collect())
+    }
+
+    /// Everything up to the matching `close`, which is consumed.
+    fn skip_group(&mut self, close: char) -> TexResult<()> {
+        let open = if close == '}' { '{' } else { '(' };
+        let mut depth = 0;
+        while let Some(c) = self.next() {
+            if c == open {
+                depth += 1;
+            } else if c == close {
+                if depth == 0 {
+                    return Ok(());
+                }
+                depth -= 1;
+            }
+        }
+        Err(self.error(&format!("unterminated group, expected {}", close)))
+    }
+
+    /// The inside of a `{...}` value, with nested braces kept. The opening brace is already gone.
+    fn braced(&mut self) -> TexResult<String> {
+        let start = self.pos;
+        self.skip_group('}')?;
+        Ok(self.src[start..self.pos - 1].iter().collect())
+    }
+
+    /// The inside of a `"..."` value. Quotes inside braces don't count.
+    fn quoted(&mut self) -> TexResult<String> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.next() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                '"' if depth == 0 => return Ok(self.src[start..self.pos - 1].iter().collect()),
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    /// `a # {b} # "c"`
+    fn value(&mut self) -> TexResult<String> {
+        let mut out = String::new();
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                Some('{') => {
+                    self.pos += 1;
+                    out.push_str(&self.braced()?);
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    out.push_str(&self.quoted()?);
+                }
+                Some(_) => {
+                    let name = self.ident()?;
+                    if name.chars().all(|x| x.is_ascii_digit()) {
+                        out.push_str(&name);
+                    } else {
+                        match self.strings.get(&name.to_lowercase()) {
+                            Some(s) => out.push_str(s),
+                            None => return Err(self.error(&format!("undefined string {}", name))),
+                        }
+                    }
+                }
+                None => return Err(self.error("expected a value")),
+            }
+            self.skip_ws();
+            if self.peek() == Some('#') {
+                self.pos += 1;
+            } else {
+                return Ok(out);
+            }
+        }
+    }
+
+    /// `name = value`
+    fn field(&mut self) -> TexResult<(String, String)> {
+        self.skip_ws();
+        let name = self.ident()?.to_lowercase();
+        self.skip_ws();
+        self.expect('=')?;
+        Ok((name, self.value()?))
+    }
+
+    /// Everything after `@type{`.
+    fn entry(&mut self, typ: &str, close: char) -> TexResult<BibEntry> {
+        self.skip_ws();
+        let key = self.ident()?;
+        let mut out = BibEntry::new(typ.into(), &key);
+        loop {
+            self.skip_ws();
+            match self.next() {
+                Some(c) if c == close => return Ok(out),
+                Some(',') => {
+                    self.skip_ws();
+                    if self.peek() == Some(close) {
+                        continue;
+                    }
+                    let (name, value) = self.field()?;
+                    out.set(&name, &value);
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error(&format!("expected , or {} in entry {}", close, key)));
+                }
+            }
+        }
+    }
+}
@@ -103,6 +103,11 @@ impl From<Frame> for Component {
     }
 }
 
+impl From<Columns> for Component {
+    fn from(value: Columns) -> Self {
+        Component::Columns(value)
+    }
+}
 impl From<Block> for Component {
     fn from(value: Block) -> Self {
         Component::Block(value)
@@ -115,6 +120,42 @@ impl From<Figure> for Component {
     }
 }
 
+impl From<MultiFigure> for Component {
+    fn from(value: MultiFigure) -> Self {
+        Component::MultiFigure(value)
+    }
+}
+
+impl From<Theorem> for Component {
+    fn from(value: Theorem) -> Self {
+        Component::Theorem(value)
+    }
+}
+
+impl From<Proof> for Component {
+    fn from(value: Proof) -> Self {
+        Component::Proof(value)
+    }
+}
+
+impl From<CodeListing> for Component {
+    fn from(value: CodeListing) -> Self {
+        Component::CodeListing(value)
+    }
+}
+
+impl From<Algorithm> for Component {
+    fn from(value: Algorithm) -> Self {
+        Component::Algorithm(value)
+    }
+}
+
+impl From<Overlaid> for Component {
+    fn from(value: Overlaid) -> Self {
+        Component::Overlaid(value)
+    }
+}
+
 impl From<Table> for Component {
     fn from(value: Table) -> Self {
         Component::Table(value)
@@ -143,6 +184,51 @@ impl From<Reference> for Component {
     }
 }
 
+impl From<Formula> for Component {
+    fn from(value: Formula) -> Self {
+        Component::Formula(value)
+    }
+}
+
+/// Inline.
+impl From<Expr> for Component {
+    fn from(value: Expr) -> Self {
+        Component::Formula(Formula::inline(value))
+    }
+}
+
+/// For the `BuiltinType`s, which are already in math mode. The chunk is `Normal`, so it adds no
+/// delimiters of its own.
+impl From<Expr> for TextChunk {
+    fn from(value: Expr) -> Self {
+        TextChunk::raw(&value.to_string())
+    }
+}
+
+impl From<Equation> for Component {
+    fn from(value: Equation) -> Self {
+        Component::Equation(value)
+    }
+}
+
+impl From<TableFloat> for Component {
+    fn from(value: TableFloat) -> Self {
+        Component::TableFloat(value)
+    }
+}
+
+impl From<Cell> for Component {
+    fn from(value: Cell) -> Self {
+        Component::Cell(value)
+    }
+}
+
+impl From<Citation> for Component {
+    fn from(value: Citation) -> Self {
+        Component::Citation(value)
+    }
+}
+
 impl From<&str> for Label {
     fn from(value: &str) -> Self {
         let q = value.find(":").unwrap_or(0);
@@ -158,6 +244,7 @@ impl From<&str> for Label {
             "lst" => Self::Code(lbl.to_string()),
             "itm" => Self::Item(lbl.to_string()),
             "alg" => Self::Algorithm(lbl.to_string()),
+            "thm" => Self::Theorem(lbl.to_string()),
             _ => Self::Standard(value.to_string()),
         }
     }
@@ -178,6 +265,7 @@ impl From<&str> for Reference {
             "lst" => Self::Code(lbl.to_string()),
             "itm" => Self::Item(lbl.to_string()),
             "alg" => Self::Algorithm(lbl.to_string()),
+            "thm" => Self::Theorem(lbl.to_string()),
             _ => Self::Standard(value.to_string()),
         }
     }
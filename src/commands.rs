@@ -4,11 +4,11 @@ use crate::prelude::*;
 
 /// Latex macros.
 /// Rudimentary so far, have to embed latex.
-/// 
+///
 /// - name: Name of the new latex macro
 /// - nargs: Number of Args
 /// - def: definition of the command.
-/// 
+///
 /// Compiles to \newcommand{\<name>}[<nargs>]{<def>}
 #[derive(Debug, Clone)]
 pub struct Command {
@@ -30,7 +30,7 @@ impl Command {
     /// I'd really prefer you try and use the `command!` macro.
     pub fn call(&self, args: Vec<&str>) -> TexResult<String> {
         if args.len() != self.nargs {
-            return Err(TexError::ArgLen.into());
+            return Err(TexError::ArgLen);
         }
         let temp = format!(
             "\\{}{}",
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+
+use crate::prelude::*;
+
+/// What went wrong. The payloads are the offending label, citation key or package name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    Error,
+    MissingPackage(String),
+    UndefinedReference(String),
+    UndefinedCitation(String),
+    OverfullBox,
+    UnderfullBox,
+    Warning,
+}
+
+/// One entry from a TeX engine log. `line` is the line of the `.tex` file the engine complained
+/// about, when the log says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub message: String,
+    pub line: Option<usize>,
+}
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.kind,
+            DiagnosticKind::Error | DiagnosticKind::MissingPackage(_)
+        )
+    }
+
+    /// The component that wrote the offending line.
+    pub fn locate<'a>(&self, map: &'a SourceMap) -> Option<&'a Span> {
+        map.locate(self.line?)
+    }
+}
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(l) => write!(f, "line {}: {}", l, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// TeX hard-wraps its log at this many characters.
+const LOG_WIDTH: usize = 79;
+
+/// Pulls errors, bad boxes, undefined references/citations and missing packages out of a
+/// `.log` file. Anything else is ignored.
+pub fn parse_log(log: &str) -> Vec<Diagnostic> {
+    let lines = unwrap_lines(log);
+    let mut out = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(msg) = line.strip_prefix("! ") {
+            // The context lines that follow an error end with `l.<line> <source>`.
+            let line = lines[i + 1..]
+                .iter()
+                .take(12)
+                .find_map(|x| x.strip_prefix("l.").and_then(leading_number));
+            out.push(Diagnostic {
+                kind: error_kind(msg),
+                message: msg.to_string(),
+                line,
+            });
+        } else if let Some((line, msg)) = file_line_error(line) {
+            // What `! ` turns into with -file-line-error.
+            out.push(Diagnostic {
+                kind: error_kind(msg),
+                message: msg.to_string(),
+                line: Some(line),
+            });
+        } else if line.starts_with("Overfull \\") || line.starts_with("Underfull \\") {
+            let kind = if line.starts_with("Overfull") {
+                DiagnosticKind::OverfullBox
+            } else {
+                DiagnosticKind::UnderfullBox
+            };
+            let line_no = after(line, "at lines ")
+                .or_else(|| after(line, "at line "))
+                .and_then(leading_number);
+            out.push(Diagnostic {
+                kind,
+                message: line.to_string(),
+                line: line_no,
+            });
+        } else if let Some((package, msg)) = warning(line) {
+            let mut msg = msg.to_string();
+            // Package warnings carry on over the lines that start with `(<package>)`.
+            if let Some(package) = package {
+                let prefix = format!("({})", package);
+                for rest in lines[i + 1..].iter().map_while(|x| x.strip_prefix(&prefix)) {
+                    msg.push(' ');
+                    msg.push_str(rest.trim());
+                }
+            }
+            let line_no = after(&msg, "on input line ").and_then(leading_number);
+            let kind = if let Some(key) = between(&msg, "Reference `", "'") {
+                DiagnosticKind::UndefinedReference(key.to_string())
+            } else if let Some(key) = between(&msg, "Citation `", "'") {
+                DiagnosticKind::UndefinedCitation(key.to_string())
+            } else {
+                DiagnosticKind::Warning
+            };
+            out.push(Diagnostic {
+                kind,
+                message: msg,
+                line: line_no,
+            });
+        }
+    }
+
+    out
+}
+
+fn error_kind(msg: &str) -> DiagnosticKind {
+    match between(msg, "File `", "' not found") {
+        Some(file) if msg.starts_with("LaTeX Error") && file.ends_with(".sty") => {
+            DiagnosticKind::MissingPackage(file.trim_end_matches(".sty").to_string())
+        }
+        _ => DiagnosticKind::Error,
+    }
+}
+
+/// Glues back together the lines TeX split at `LOG_WIDTH`. A line that happens to be exactly
+/// that long is left alone when the next one starts a diagnostic of its own.
+fn unwrap_lines(log: &str) -> Vec<String> {
+    let mut out: Vec<String> = vec![];
+    let mut continued = false;
+    for line in log.lines() {
+        match out.last_mut() {
+            Some(last) if continued && !starts_diagnostic(line) => last.push_str(line),
+            _ => out.push(line.to_string()),
+        }
+        continued = line.chars().count() == LOG_WIDTH;
+    }
+    out
+}
+
+fn starts_diagnostic(line: &str) -> bool {
+    [
+        "!",
+        "LaTeX ",
+        "Package ",
+        "Class ",
+        "Overfull \\",
+        "Underfull \\",
+    ]
+    .iter()
+    .any(|x| line.starts_with(x))
+        || file_line_error(line).is_some()
+}
+
+fn leading_number(s: &str) -> Option<usize> {
+    let digits = s
+        .trim_start()
+        .chars()
+        .take_while(|x| x.is_ascii_digit())
+        .collect::<String>();
+    digits.parse().ok()
+}
+
+fn after<'a>(s: &'a str, pat: &str) -> Option<&'a str> {
+    s.find(pat).map(|i| &s[i + pat.len()..])
+}
+
+fn between<'a>(s: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let rest = after(s, start)?;
+    rest.find(end).map(|i| &rest[..i])
+}
+
+/// `./main.tex:12: Undefined control sequence.`
+fn file_line_error(line: &str) -> Option<(usize, &str)> {
+    let (file, rest) = line.split_once(".tex:")?;
+    if file.contains(' ') {
+        return None;
+    }
+    let (num, msg) = rest.split_once(": ")?;
+    Some((num.parse().ok()?, msg))
+}
+
+/// The message of a `LaTeX Warning: ...` or `Package foo Warning: ...` line, along with the
+/// package (or class) it's from.
+fn warning(line: &str) -> Option<(Option<&str>, &str)> {
+    if let Some(msg) = line.strip_prefix("LaTeX Warning: ") {
+        return Some((None, msg));
+    }
+    let rest = line
+        .strip_prefix("Package ")
+        .or_else(|| line.strip_prefix("Class "))?;
+    let (name, msg) = rest.split_once(" Warning: ")?;
+    (!name.contains(' ')).then_some((Some(name), msg))
+}
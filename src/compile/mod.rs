@@ -0,0 +1,324 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{self, Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::prelude::*;
+
+mod log;
+
+pub use log::*;
+
+/// The program that turns the `.tex` into a pdf.
+/// `Custom` takes the path of any executable that accepts the `.tex` file as its last argument,
+/// which is handy for wrappers (and for tests).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Engine {
+    Pdflatex,
+    Xelatex,
+    Lualatex,
+    Latexmk,
+    Tectonic,
+    Custom(String),
+}
+impl Engine {
+    pub fn program(&self) -> &str {
+        match &self {
+            Engine::Pdflatex => "pdflatex",
+            Engine::Xelatex => "xelatex",
+            Engine::Lualatex => "lualatex",
+            Engine::Latexmk => "latexmk",
+            Engine::Tectonic => "tectonic",
+            Engine::Custom(s) => s,
+        }
+    }
+
+    fn default_args(&self) -> Vec<String> {
+        let args: &[&str] = match &self {
+            Engine::Pdflatex | Engine::Xelatex | Engine::Lualatex => {
+                &["-interaction=nonstopmode", "-halt-on-error"]
+            }
+            Engine::Latexmk => &["-pdf", "-interaction=nonstopmode", "-halt-on-error"],
+            Engine::Tectonic | Engine::Custom(_) => &[],
+        };
+        args.iter().map(|x| x.to_string()).collect()
+    }
+
+    /// Plain engines need a second run for references and the table of contents.
+    /// `latexmk` and `tectonic` figure that out on their own.
+    fn default_passes(&self) -> usize {
+        match &self {
+            Engine::Pdflatex | Engine::Xelatex | Engine::Lualatex => 2,
+            Engine::Latexmk | Engine::Tectonic | Engine::Custom(_) => 1,
+        }
+    }
+
+    /// What lets the engine run other programs, like minted's Pygments.
+    fn shell_escape(&self) -> Option<&'static str> {
+        match &self {
+            Engine::Pdflatex | Engine::Xelatex | Engine::Lualatex | Engine::Latexmk => {
+                Some("-shell-escape")
+            }
+            Engine::Tectonic => Some("-Zshell-escape"),
+            Engine::Custom(_) => None,
+        }
+    }
+
+    /// Whether `bibtex`/`biber` has to be run by hand, in between passes.
+    fn needs_bib_tool(&self) -> bool {
+        matches!(self, Engine::Pdflatex | Engine::Xelatex | Engine::Lualatex)
+    }
+}
+impl From<&str> for Engine {
+    fn from(value: &str) -> Self {
+        match value {
+            "pdflatex" => Self::Pdflatex,
+            "xelatex" => Self::Xelatex,
+            "lualatex" => Self::Lualatex,
+            "latexmk" => Self::Latexmk,
+            "tectonic" => Self::Tectonic,
+            _ => Self::Custom(value.to_string()),
+        }
+    }
+}
+
+/// Everything we know about a failed engine run.
+#[derive(Debug, Clone)]
+pub struct CompileFailure {
+    pub engine: Engine,
+    /// 1-based.
+    pub pass: usize,
+    /// `None` if the engine was killed by a signal.
+    pub status: Option<i32>,
+    /// Contents of the `.log` file, or the engine's stdout if there isn't one.
+    pub log: String,
+    /// `log`, parsed.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Where each component of the document ended up in the `.tex`.
+    pub source_map: SourceMap,
+    pub build_dir: PathBuf,
+}
+impl CompileFailure {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|x| x.is_error())
+    }
+
+    /// Every diagnostic, next to the component responsible for it (if it could be found).
+    pub fn located(&self) -> Vec<(&Diagnostic, Option<&Span>)> {
+        self.diagnostics
+            .iter()
+            .map(|x| (x, x.locate(&self.source_map)))
+            .collect()
+    }
+}
+
+/// Turns a `Document` into a pdf.
+///
+/// The document is written to `<build_dir>/<jobname>.tex`, every image it refers to is copied over
+/// from the document's graphics path, and the engine is run the required number of times.
+/// The build directory is left in place, so the pdf (and the log) can be picked up afterwards.
+///
+/// ```rust,no_run
+/// use rust_texas::prelude::*;
+/// fn dummy() -> TexResult<()> {
+///     let doc = document!("article");
+///     let pdf = Compiler::new(Engine::Latexmk).compile(&doc)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Compiler {
+    engine: Engine,
+    args: Vec<String>,
+    passes: Option<usize>,
+    build_dir: Option<PathBuf>,
+    jobname: String,
+}
+impl Compiler {
+    pub fn new(engine: Engine) -> Self {
+        Self {
+            engine,
+            args: vec![],
+            passes: None,
+            build_dir: None,
+            jobname: "main".to_string(),
+        }
+    }
+
+    /// Extra command line arguments, on top of the engine's defaults.
+    pub fn add_arg(&mut self, arg: &str) -> &mut Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Overrides the number of times the engine is run.
+    pub fn set_passes(&mut self, passes: usize) -> &mut Self {
+        self.passes = Some(passes);
+        self
+    }
+
+    /// Defaults to a fresh directory under the system temp directory.
+    pub fn set_build_dir(&mut self, path: &str) -> &mut Self {
+        self.build_dir = Some(PathBuf::from(path));
+        self
+    }
+
+    pub fn set_jobname(&mut self, jobname: &str) -> &mut Self {
+        self.jobname = jobname.to_string();
+        self
+    }
+
+    /// Returns the path of the generated pdf.
+    ///
+    /// If the document has a bibliography in a `.bib` file, that is written too, and for the plain
+    /// engines `bibtex` (or `biber`) is run after the first pass, followed by at least two more.
+    /// Documents that load `minted` are compiled with shell escape on.
+    pub fn compile(&self, doc: &Document) -> TexResult<PathBuf> {
+        let dir = match &self.build_dir {
+            Some(dir) => dir.clone(),
+            None => fresh_build_dir(),
+        };
+        fs::create_dir_all(&dir)?;
+
+        let tex = format!("{}.tex", self.jobname);
+        let mut out = BufWriter::new(File::create(dir.join(&tex))?);
+        let source_map = doc.write_mapped(&mut out)?;
+        // Dropping the writer would flush it too, but without telling anyone if that failed.
+        out.flush()?;
+        copy_images(doc, &dir)?;
+        let bib_tool = match doc.bibliography() {
+            Some(bib) => {
+                bib.write_file(&dir)?;
+                bib.tool().filter(|_| self.engine.needs_bib_tool())
+            }
+            None => None,
+        };
+
+        let mut args = self.engine.default_args();
+        if doc.packages()?.iter().any(|x| x.name() == "minted") {
+            args.extend(self.engine.shell_escape().map(|x| x.to_string()));
+        }
+        args.extend(self.args.iter().cloned());
+        args.push(tex);
+
+        let mut passes = self.passes.unwrap_or(self.engine.default_passes());
+        if bib_tool.is_some() && self.passes.is_none() {
+            passes = passes.max(3);
+        }
+        for pass in 1..=passes {
+            self.run(&self.engine, &args, "log", pass, &dir, &source_map)?;
+            if let (1, Some(tool)) = (pass, bib_tool) {
+                self.run(
+                    &Engine::Custom(tool.to_string()),
+                    std::slice::from_ref(&self.jobname),
+                    "blg",
+                    pass,
+                    &dir,
+                    &source_map,
+                )?;
+            }
+        }
+
+        let pdf = dir.join(format!("{}.pdf", self.jobname));
+        if !pdf.exists() {
+            return Err(TexError::WhatEven(format!(
+                "{} finished, but {} does not exist.",
+                self.engine.program(),
+                pdf.display()
+            )));
+        }
+        Ok(pdf)
+    }
+
+    /// Runs `engine` once in `dir`. On failure, the log is read from `<jobname>.<log>`.
+    fn run(
+        &self,
+        engine: &Engine,
+        args: &[String],
+        log: &str,
+        pass: usize,
+        dir: &Path,
+        source_map: &SourceMap,
+    ) -> TexResult<()> {
+        let out = process::Command::new(engine.program())
+            .args(args)
+            .current_dir(dir)
+            .output()?;
+        if out.status.success() {
+            return Ok(());
+        }
+        let log = fs::read_to_string(dir.join(format!("{}.{}", self.jobname, log)))
+            .unwrap_or_else(|_| String::from_utf8_lossy(&out.stdout).to_string());
+        Err(TexError::CompileFailed(Box::new(CompileFailure {
+            engine: engine.clone(),
+            pass,
+            status: out.status.code(),
+            diagnostics: parse_log(&log),
+            log,
+            source_map: source_map.clone(),
+            build_dir: dir.to_path_buf(),
+        })))
+    }
+}
+
+fn fresh_build_dir() -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "texas-{}-{}-{}",
+        process::id(),
+        nanos,
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Extensions graphicx tries when \includegraphics is given a bare name.
+const IMAGE_EXTENSIONS: [&str; 6] = ["pdf", "png", "jpg", "jpeg", "eps", "svg"];
+
+/// Copies every image into the build directory, under the same relative path it is included with.
+/// Sources are looked up in the document's graphics path, relative to the current directory.
+/// Absolute paths, and paths that would land outside the build directory, are left alone.
+fn copy_images(doc: &Document, dir: &Path) -> TexResult<()> {
+    for img in doc.images() {
+        let rel = Path::new(img.path());
+        if !rel
+            .components()
+            .all(|x| matches!(x, path::Component::Normal(_) | path::Component::CurDir))
+        {
+            continue;
+        }
+        let found = doc
+            .graphics_path()
+            .iter()
+            .flat_map(|gp| {
+                let base = Path::new(gp).join(rel);
+                let mut candidates = vec![base.clone()];
+                if rel.extension().is_none() {
+                    candidates.extend(IMAGE_EXTENSIONS.iter().map(|e| base.with_extension(e)));
+                }
+                candidates
+            })
+            .find(|x| x.is_file());
+        let src = match found {
+            Some(src) => src,
+            None => return Err(TexError::MissingImage(img.path().to_string())),
+        };
+
+        let dst = match src.extension() {
+            Some(ext) if rel.extension().is_none() => dir.join(rel).with_extension(ext),
+            _ => dir.join(rel),
+        };
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(src, dst)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// Which package typesets the pseudocode. Only one of them can be used in a document, since
+/// both define the `algorithm` float.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlgorithmBackend {
+    /// `algorithm` and `algpseudocode` (from algorithmicx).
+    #[default]
+    Algpseudocode,
+    Algorithm2e,
+}
+
+/// One line, or one block, of pseudocode. Conditions, statements and the like are latex, so
+/// `$x \gets 0$` works as you'd expect.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    State(String),
+    If {
+        cond: String,
+        then: Vec<Step>,
+        /// `else if`s, in order.
+        elifs: Vec<(String, Vec<Step>)>,
+        otherwise: Option<Vec<Step>>,
+    },
+    For(String, Vec<Step>),
+    While(String, Vec<Step>),
+    /// The body, then the condition it repeats until.
+    Repeat(Vec<Step>, String),
+    /// Name, arguments and body.
+    Function(String, String, Vec<Step>),
+    Return(String),
+    Comment(String),
+}
+impl Step {
+    pub fn state(s: &str) -> Self {
+        Step::State(s.to_string())
+    }
+
+    pub fn if_then(cond: &str, then: Vec<Step>) -> Self {
+        Step::If {
+            cond: cond.to_string(),
+            then,
+            elifs: vec![],
+            otherwise: None,
+        }
+    }
+
+    /// Adds an `else if` to an `If`. Does nothing to anything else.
+    pub fn else_if(mut self, c: &str, steps: Vec<Step>) -> Self {
+        if let Step::If { elifs, .. } = &mut self {
+            elifs.push((c.to_string(), steps));
+        }
+        self
+    }
+
+    /// Adds (or replaces) the `else` of an `If`. Does nothing to anything else.
+    pub fn otherwise(mut self, steps: Vec<Step>) -> Self {
+        if let Step::If { otherwise, .. } = &mut self {
+            *otherwise = Some(steps);
+        }
+        self
+    }
+
+    pub fn for_each(head: &str, body: Vec<Step>) -> Self {
+        Step::For(head.to_string(), body)
+    }
+
+    pub fn while_do(cond: &str, body: Vec<Step>) -> Self {
+        Step::While(cond.to_string(), body)
+    }
+
+    pub fn repeat(body: Vec<Step>, until: &str) -> Self {
+        Step::Repeat(body, until.to_string())
+    }
+
+    pub fn function(name: &str, args: &str, body: Vec<Step>) -> Self {
+        Step::Function(name.to_string(), args.to_string(), body)
+    }
+
+    pub fn ret(s: &str) -> Self {
+        Step::Return(s.to_string())
+    }
+
+    pub fn comment(s: &str) -> Self {
+        Step::Comment(s.to_string())
+    }
+
+    fn has_function(&self) -> bool {
+        match &self {
+            Step::Function(..) => true,
+            Step::If {
+                then,
+                elifs,
+                otherwise,
+                ..
+            } => then
+                .iter()
+                .chain(elifs.iter().flat_map(|x| &x.1))
+                .chain(otherwise.iter().flatten())
+                .any(|x| x.has_function()),
+            Step::For(_, body) | Step::While(_, body) | Step::Repeat(body, _) => {
+                body.iter().any(|x| x.has_function())
+            }
+            _ => false,
+        }
+    }
+
+    fn write_algpseudocode(&self, depth: usize, w: &mut dyn Write) -> io::Result<()> {
+        let pad = "  ".repeat(depth);
+        let body = |steps: &[Step], w: &mut dyn Write| -> io::Result<()> {
+            for s in steps {
+                s.write_algpseudocode(depth + 1, w)?;
+            }
+            Ok(())
+        };
+        match &self {
+            Step::State(s) => writeln!(w, "{}\\State {}", pad, s),
+            Step::If {
+                cond,
+                then,
+                elifs,
+                otherwise,
+            } => {
+                writeln!(w, "{}\\If{{{}}}", pad, cond)?;
+                body(then, w)?;
+                for (c, steps) in elifs {
+                    writeln!(w, "{}\\ElsIf{{{}}}", pad, c)?;
+                    body(steps, w)?;
+                }
+                if let Some(steps) = otherwise {
+                    writeln!(w, "{}\\Else", pad)?;
+                    body(steps, w)?;
+                }
+                writeln!(w, "{}\\EndIf", pad)
+            }
+            Step::For(head, steps) => {
+                writeln!(w, "{}\\For{{{}}}", pad, head)?;
+                body(steps, w)?;
+                writeln!(w, "{}\\EndFor", pad)
+            }
+            Step::While(cond, steps) => {
+                writeln!(w, "{}\\While{{{}}}", pad, cond)?;
+                body(steps, w)?;
+                writeln!(w, "{}\\EndWhile", pad)
+            }
+            Step::Repeat(steps, until) => {
+                writeln!(w, "{}\\Repeat", pad)?;
+                body(steps, w)?;
+                writeln!(w, "{}\\Until{{{}}}", pad, until)
+            }
+            Step::Function(name, args, steps) => {
+                writeln!(w, "{}\\Function{{{}}}{{{}}}", pad, name, args)?;
+                body(steps, w)?;
+                writeln!(w, "{}\\EndFunction", pad)
+            }
+            Step::Return(s) => writeln!(w, "{}\\State \\Return {}", pad, s),
+            Step::Comment(s) => writeln!(w, "{}\\State \\Comment{{{}}}", pad, s),
+        }
+    }
+
+    fn write_algorithm2e(&self, depth: usize, w: &mut dyn Write) -> io::Result<()> {
+        let pad = "  ".repeat(depth);
+        let body = |steps: &[Step], w: &mut dyn Write| -> io::Result<()> {
+            for s in steps {
+                s.write_algorithm2e(depth + 1, w)?;
+            }
+            Ok(())
+        };
+        match &self {
+            Step::State(s) => writeln!(w, "{}{}\\;", pad, s),
+            Step::If {
+                cond,
+                then,
+                elifs,
+                otherwise,
+            } => {
+                // The u-versions don't close the block with an "end", so they can be chained.
+                let chained = !elifs.is_empty() || otherwise.is_some();
+                writeln!(
+                    w,
+                    "{}\\{}{{{}}}{{",
+                    pad,
+                    if chained { "uIf" } else { "If" },
+                    cond
+                )?;
+                body(then, w)?;
+                writeln!(w, "{}}}", pad)?;
+                for (i, (c, steps)) in elifs.iter().enumerate() {
+                    let last = i + 1 == elifs.len() && otherwise.is_none();
+                    writeln!(
+                        w,
+                        "{}\\{}{{{}}}{{",
+                        pad,
+                        if last { "ElseIf" } else { "uElseIf" },
+                        c
+                    )?;
+                    body(steps, w)?;
+                    writeln!(w, "{}}}", pad)?;
+                }
+                if let Some(steps) = otherwise {
+                    writeln!(w, "{}\\Else{{", pad)?;
+                    body(steps, w)?;
+                    writeln!(w, "{}}}", pad)?;
+                }
+                Ok(())
+            }
+            Step::For(head, steps) => {
+                writeln!(w, "{}\\For{{{}}}{{", pad, head)?;
+                body(steps, w)?;
+                writeln!(w, "{}}}", pad)
+            }
+            Step::While(cond, steps) => {
+                writeln!(w, "{}\\While{{{}}}{{", pad, cond)?;
+                body(steps, w)?;
+                writeln!(w, "{}}}", pad)
+            }
+            Step::Repeat(steps, until) => {
+                writeln!(w, "{}\\Repeat{{{}}}{{", pad, until)?;
+                body(steps, w)?;
+                writeln!(w, "{}}}", pad)
+            }
+            Step::Function(name, args, steps) => {
+                writeln!(w, "{}\\Fn{{\\textsc{{{}}}({})}}{{", pad, name, args)?;
+                body(steps, w)?;
+                writeln!(w, "{}}}", pad)
+            }
+            Step::Return(s) => writeln!(w, "{}\\Return{{{}}}\\;", pad, s),
+            Step::Comment(s) => writeln!(w, "{}\\tcp{{{}}}", pad, s),
+        }
+    }
+}
+
+/// The `algorithm` float: pseudocode with a caption and a label, so that it gets a number.
+#[derive(Debug, Clone)]
+pub struct Algorithm {
+    caption: String,
+    pub(crate) label: Option<Label>,
+    steps: Vec<Step>,
+    backend: AlgorithmBackend,
+    line_numbers: bool,
+}
+impl Algorithm {
+    pub fn new(caption: &str) -> Self {
+        Self {
+            caption: caption.to_string(),
+            label: None,
+            steps: vec![],
+            backend: AlgorithmBackend::Algpseudocode,
+            line_numbers: false,
+        }
+    }
+
+    pub fn add_step(&mut self, step: Step) -> &mut Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Labels it `alg:<name>`.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Algorithm(name.to_string()));
+        self
+    }
+
+    pub fn set_backend(&mut self, backend: AlgorithmBackend) -> &mut Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn set_line_numbers(&mut self, line_numbers: bool) -> &mut Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn backend(&self) -> AlgorithmBackend {
+        self.backend
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        match self.backend {
+            AlgorithmBackend::Algpseudocode => {
+                vec![package!("algorithm"), package!("algpseudocode")]
+            }
+            AlgorithmBackend::Algorithm2e => vec![package!("algorithm2e")],
+        }
+    }
+}
+impl AsLatex for Algorithm {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "\\begin{{algorithm}}")?;
+        writeln!(w, "\\caption{{{}}}", self.caption)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        match self.backend {
+            AlgorithmBackend::Algpseudocode => {
+                write!(w, "\\begin{{algorithmic}}")?;
+                if self.line_numbers {
+                    write!(w, "[1]")?;
+                }
+                writeln!(w)?;
+                for s in &self.steps {
+                    s.write_algpseudocode(1, w)?;
+                }
+                writeln!(w, "\\end{{algorithmic}}")?;
+            }
+            AlgorithmBackend::Algorithm2e => {
+                if self.line_numbers {
+                    writeln!(w, "\\LinesNumbered")?;
+                }
+                if self.steps.iter().any(|x| x.has_function()) {
+                    writeln!(w, "\\SetKwProg{{Fn}}{{Function}}{{:}}{{end}}")?;
+                }
+                for s in &self.steps {
+                    s.write_algorithm2e(0, w)?;
+                }
+            }
+        }
+        writeln!(w, "\\end{{algorithm}}")
+    }
+}
@@ -1,22 +1,47 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
 use crate::prelude::*;
 
+/// The kinds of block beamer has, which the theme colors differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockKind {
+    #[default]
+    Block,
+    Alert,
+    Example,
+}
+impl BlockKind {
+    pub fn env(&self) -> &'static str {
+        match &self {
+            BlockKind::Block => "block",
+            BlockKind::Alert => "alertblock",
+            BlockKind::Example => "exampleblock",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Block {
-    title: String,
-    components: Vec<Component>,
+    pub(crate) title: String,
+    pub(crate) components: Vec<Component>,
+    kind: BlockKind,
+    overlay: Option<Overlay>,
 }
 
 impl AsLatex for Block {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!(
-            "\\begin{{block}}{{{}}} \n {} \\end{{block}} \n ",
-            self.title, comps
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{{}}}", self.kind.env())?;
+        if let Some(o) = &self.overlay {
+            write!(w, "{}", o)?;
+        }
+        write!(w, "{{{}}} \n ", self.title)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \\end{{{}}} \n ", self.kind.env())
     }
 }
 
@@ -39,10 +64,7 @@ impl Populate for Block {
 
 impl Block {
     pub fn new(title: &str) -> Self {
-        Self {
-            title: title.to_string(),
-            components: vec![],
-        }
+        Self::with_components(title, vec![])
     }
 
     pub fn new_untitled() -> Self {
@@ -57,27 +79,99 @@ impl Block {
         Self {
             title: title.to_string(),
             components,
+            kind: BlockKind::Block,
+            overlay: None,
+        }
+    }
+
+    /// `alertblock`, for warnings and the like.
+    pub fn alert(title: &str) -> Self {
+        let mut out = Self::new(title);
+        out.kind = BlockKind::Alert;
+        out
+    }
+
+    /// `exampleblock`.
+    pub fn example(title: &str) -> Self {
+        let mut out = Self::new(title);
+        out.kind = BlockKind::Example;
+        out
+    }
+
+    pub fn set_kind(&mut self, kind: BlockKind) -> &mut Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn kind(&self) -> BlockKind {
+        self.kind
+    }
+
+    /// The slides the block is shown on: `\begin{block}<2->{...}`.
+    pub fn set_overlay(&mut self, overlay: Overlay) -> &mut Self {
+        self.overlay = Some(overlay);
+        self
+    }
+}
+
+/// The options of a frame: `\begin{frame}[fragile, plain]{...}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameOption {
+    /// Needed for verbatim text and code listings. Frames add it themselves when they have those.
+    Fragile,
+    /// No headline, footline or sidebars.
+    Plain,
+    /// Spreads the content over as many slides as it needs.
+    AllowFrameBreaks,
+    /// Shrinks the content to fit, by at least this many percent if given.
+    Shrink(Option<u32>),
+    /// A name for the frame, for `\againframe` and `\hyperlink`.
+    Label(String),
+}
+impl Display for FrameOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            FrameOption::Fragile => write!(f, "fragile"),
+            FrameOption::Plain => write!(f, "plain"),
+            FrameOption::AllowFrameBreaks => write!(f, "allowframebreaks"),
+            FrameOption::Shrink(None) => write!(f, "shrink"),
+            FrameOption::Shrink(Some(p)) => write!(f, "shrink={}", p),
+            FrameOption::Label(l) => write!(f, "label={}", l),
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct Frame {
-    title: String,
-    components: Vec<Component>,
+    pub(crate) title: String,
+    subtitle: Option<String>,
+    opt: Vec<FrameOption>,
+    notes: Vec<String>,
+    pub(crate) components: Vec<Component>,
 }
 
 impl AsLatex for Frame {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!(
-            "\\begin{{frame}}{{{}}} \n {} \\end{{frame}} \n ",
-            self.title, comps
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut opt = self.opt.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        if !self.opt.contains(&FrameOption::Fragile) && self.needs_fragile() {
+            opt.insert(0, FrameOption::Fragile.to_string());
+        }
+        write!(w, "\\begin{{frame}}")?;
+        if !opt.is_empty() {
+            write!(w, "[{}]", opt.join(", "))?;
+        }
+        write!(w, "{{{}}}", self.title)?;
+        if let Some(s) = &self.subtitle {
+            write!(w, "{{{}}}", s)?;
+        }
+        write!(w, " \n ")?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        for n in &self.notes {
+            write!(w, "\\note{{{}}} \n ", n)?;
+        }
+        write!(w, " \\end{{frame}} \n ")
     }
 }
 
@@ -99,10 +193,7 @@ impl Populate for Frame {
 
 impl Frame {
     pub fn new(title: &str) -> Self {
-        Self {
-            title: title.to_string(),
-            components: vec![],
-        }
+        Self::with_components(title, vec![])
     }
 
     pub fn new_untitled() -> Self {
@@ -116,7 +207,203 @@ impl Frame {
     pub fn with_components(title: &str, components: Vec<Component>) -> Self {
         Self {
             title: title.to_string(),
+            subtitle: None,
+            opt: vec![],
+            notes: vec![],
+            components,
+        }
+    }
+
+    /// Speaker notes, only shown if `Presentation::set_notes` says so.
+    pub fn add_note(&mut self, note: &str) -> &mut Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn set_subtitle(&mut self, subtitle: &str) -> &mut Self {
+        self.subtitle = Some(subtitle.to_string());
+        self
+    }
+
+    /// Replaces the option of the same kind, if there is one.
+    pub fn add_frame_option(&mut self, opt: FrameOption) -> &mut Self {
+        let kind = std::mem::discriminant(&opt);
+        match self
+            .opt
+            .iter_mut()
+            .find(|x| std::mem::discriminant(*x) == kind)
+        {
+            Some(existing) => *existing = opt,
+            None => self.opt.push(opt),
+        }
+        self
+    }
+
+    pub fn frame_options(&self) -> &[FrameOption] {
+        &self.opt
+    }
+
+    /// Whether there is verbatim text, a code listing or a `verbatim`, `lstlisting` or `minted`
+    /// environment in it, which beamer can only typeset in a fragile frame.
+    pub fn needs_fragile(&self) -> bool {
+        let mut out = false;
+        for c in &self.components {
+            c.walk(&mut |x| {
+                out |= match x {
+                    Component::TextChunk(t) => matches!(t.typ, TextType::Verbatim),
+                    Component::CodeListing(_) => true,
+                    Component::Environment(e) => matches!(
+                        e.name.as_str(),
+                        "verbatim" | "verbatim*" | "lstlisting" | "minted"
+                    ),
+                    _ => false,
+                }
+            });
+        }
+        out
+    }
+}
+
+/// Where columns sit vertically, relative to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnAlignment {
+    /// Top lines aligned.
+    Top,
+    #[default]
+    Center,
+    Bottom,
+    /// Tops aligned, even when a column starts with a picture rather than a line of text.
+    TopEdge,
+}
+impl ColumnAlignment {
+    pub fn spec(&self) -> &'static str {
+        match &self {
+            ColumnAlignment::Top => "t",
+            ColumnAlignment::Center => "c",
+            ColumnAlignment::Bottom => "b",
+            ColumnAlignment::TopEdge => "T",
+        }
+    }
+}
+
+/// One column of a `Columns`, `width` being a fraction of `\textwidth`.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub(crate) width: f64,
+    align: Option<ColumnAlignment>,
+    pub(crate) components: Vec<Component>,
+}
+impl AsLatex for Column {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{column}}")?;
+        if let Some(a) = self.align {
+            write!(w, "[{}]", a.spec())?;
+        }
+        write!(w, "{{{}}} \n ", Length::TextWidth(self.width))?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \\end{{column}} \n ")
+    }
+}
+impl Populate for Column {
+    fn attach(&mut self, other: Component) -> TexResult<&mut Self> {
+        self.components.push(other);
+        Ok(self)
+    }
+    fn attach_vec(&mut self, mut other: Vec<Component>) -> TexResult<&mut Self> {
+        self.components.append(&mut other);
+        Ok(self)
+    }
+
+    fn attach_iter<I: Iterator<Item = Component>>(&mut self, other: I) -> TexResult<&mut Self> {
+        self.components.extend(other);
+        Ok(self)
+    }
+}
+impl Column {
+    pub fn new(width: f64) -> Self {
+        Self::with_components(width, vec![])
+    }
+
+    pub fn with_components(width: f64, components: Vec<Component>) -> Self {
+        Self {
+            width,
+            align: None,
             components,
         }
     }
+
+    /// Overrides the alignment of the `Columns` it's in, for this column only.
+    pub fn set_alignment(&mut self, align: ColumnAlignment) -> &mut Self {
+        self.align = Some(align);
+        self
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+}
+
+/// Side by side columns in a frame: text on the left, a plot on the right.
+#[derive(Debug, Clone, Default)]
+pub struct Columns {
+    pub(crate) columns: Vec<Column>,
+    align: ColumnAlignment,
+}
+impl AsLatex for Columns {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{columns}}")?;
+        if self.align != ColumnAlignment::Center {
+            write!(w, "[{}]", self.align.spec())?;
+        }
+        write!(w, " \n ")?;
+        for c in &self.columns {
+            c.write_to(w)?;
+        }
+        write!(w, " \\end{{columns}} \n ")
+    }
+}
+impl Columns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_alignment(&mut self, align: ColumnAlignment) -> &mut Self {
+        self.align = align;
+        self
+    }
+
+    /// Fails if the column has no width, or if the columns would be wider than the text.
+    pub fn add_column(&mut self, column: Column) -> TexResult<&mut Self> {
+        if !Length::TextWidth(column.width).is_positive() {
+            return Err(TexError::InvalidOptions(format!(
+                "a column can't be {} of the text wide",
+                column.width
+            )));
+        }
+        let total = self.total_width() + column.width;
+        // A little slack, so that three columns of 1/3 fit.
+        if total > 1.0 + 1e-9 {
+            return Err(TexError::InvalidOptions(format!(
+                "the columns would take up {} of the text width",
+                total
+            )));
+        }
+        self.columns.push(column);
+        Ok(self)
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// As a fraction of `\textwidth`.
+    pub fn total_width(&self) -> f64 {
+        self.columns.iter().map(|x| x.width).sum()
+    }
 }
@@ -1,4 +1,7 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
 
 use crate::prelude::*;
 
@@ -7,8 +10,8 @@ pub struct Builtin {
     typ: BuiltinType,
 }
 impl AsLatex for Builtin {
-    fn to_string(&self) -> String {
-        format!("{}", self.typ.to_string())
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "{}", self.typ)
     }
 }
 impl Builtin {
@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use crate::prelude::*;
+
+/// Anything that can be a row of a table. The cells are plain text; they are escaped on the
+/// way in.
+///
+/// ```rust
+/// use rust_texas::prelude::*;
+/// struct Sale { region: String, amount: f64 }
+/// impl TableRow for Sale {
+///     fn columns() -> Vec<String> {
+///         vec!["Region".to_string(), "Amount".to_string()]
+///     }
+///     fn cells(&self) -> Vec<String> {
+///         vec![self.region.clone(), self.amount.to_string()]
+///     }
+/// }
+/// fn dummy() -> TexResult<()> {
+///     let sales = vec![Sale { region: "R&D".to_string(), amount: 1234.5 }];
+///     let mut table = Table::from_records(&sales)?;
+///     table.format_numbers(1, NumberFormat::Grouped(2));
+///     Ok(())
+/// }
+/// ```
+pub trait TableRow {
+    fn columns() -> Vec<String>;
+    fn cells(&self) -> Vec<String>;
+}
+
+impl<T: TableRow> TableRow for &T {
+    fn columns() -> Vec<String> {
+        T::columns()
+    }
+
+    fn cells(&self) -> Vec<String> {
+        (*self).cells()
+    }
+}
+
+/// How `Table::format_numbers` writes numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// This many decimals.
+    Fixed(usize),
+    /// This many decimals, and a comma between groups of thousands.
+    Grouped(usize),
+    /// Times 100, this many decimals, and a `\%`.
+    Percent(usize),
+}
+impl NumberFormat {
+    pub fn format(&self, n: f64) -> String {
+        match *self {
+            NumberFormat::Fixed(d) => format!("{:.*}", d, n),
+            NumberFormat::Grouped(d) => {
+                let s = format!("{:.*}", d, n.abs());
+                let (int, frac) = s.split_at(s.find('.').unwrap_or(s.len()));
+                let mut grouped = String::new();
+                for (i, c) in int.chars().enumerate() {
+                    if i > 0 && (int.len() - i) % 3 == 0 {
+                        grouped.push(',');
+                    }
+                    grouped.push(c);
+                }
+                let sign = if n < 0.0 && s.chars().any(|x| x != '0' && x != '.') {
+                    "-"
+                } else {
+                    ""
+                };
+                format!("{}{}{}", sign, grouped, frac)
+            }
+            NumberFormat::Percent(d) => format!("{:.*}\\%", d, n * 100.0),
+        }
+    }
+}
+
+/// A row of escaped text cells.
+fn text_row<S: AsRef<str>>(cells: &[S]) -> Row {
+    Row::with_cells(
+        cells
+            .iter()
+            .map(|x| Component::TextChunk(TextChunk::raw(&escape_text(x.as_ref()))))
+            .collect(),
+    )
+}
+
+impl Table {
+    /// One row per `Vec`, under the given header. Every cell is escaped. Fails if a row isn't as
+    /// wide as the header.
+    pub fn from_data<T: Display>(head: &[&str], rows: Vec<Vec<T>>) -> TexResult<Self> {
+        let mut out = Self::new(head.len(), text_row(head));
+        for (i, r) in rows.iter().enumerate() {
+            if r.len() != head.len() {
+                return Err(TexError::TableWidth(i + 1, head.len(), r.len()));
+            }
+            let cells = r.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+            out.rows.push(Component::Row(text_row(&cells)));
+        }
+        Ok(out)
+    }
+
+    /// The header comes from `R::columns`, one row per record. Fails if a record's cells
+    /// aren't as many as the columns.
+    pub fn from_records<R: TableRow, I: IntoIterator<Item = R>>(records: I) -> TexResult<Self> {
+        let head = R::columns();
+        let mut out = Self::new(head.len(), text_row(&head));
+        for (i, r) in records.into_iter().enumerate() {
+            let cells = r.cells();
+            if cells.len() != head.len() {
+                return Err(TexError::TableWidth(i + 1, head.len(), cells.len()));
+            }
+            out.rows.push(Component::Row(text_row(&cells)));
+        }
+        Ok(out)
+    }
+
+    /// The first record is the header. Every cell is escaped. Rows of the wrong length are an
+    /// error, as is anything else the csv reader doesn't like.
+    #[cfg(feature = "csv")]
+    pub fn from_csv<R: std::io::Read>(reader: R) -> TexResult<Self> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let csv_error = |e: csv::Error| TexError::CsvError(e.to_string());
+        let head = reader.headers().map_err(csv_error)?.clone();
+        let mut out = Self::new(head.len(), text_row(&head.iter().collect::<Vec<_>>()));
+        for record in reader.records() {
+            let record = record.map_err(csv_error)?;
+            out.rows
+                .push(Component::Row(text_row(&record.iter().collect::<Vec<_>>())));
+        }
+        Ok(out)
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn from_csv_file(path: &str) -> TexResult<Self> {
+        Self::from_csv(std::fs::File::open(path)?)
+    }
+
+    /// Rewrites every cell of column `col` (0-based) that holds a plain, finite number, and
+    /// right-aligns the column. Anything else in the column, header included, is left alone, so
+    /// "NaN" and "inf" stay as they are.
+    pub fn format_numbers(&mut self, col: usize, format: NumberFormat) -> &mut Self {
+        for row in self.rows.iter_mut() {
+            let Component::Row(row) = row else { continue };
+            let Some(Component::TextChunk(cell)) = row.cells.get_mut(col) else {
+                continue;
+            };
+            if !matches!(cell.typ(), TextType::Normal) {
+                continue;
+            }
+            match cell.body().trim().parse::<f64>() {
+                Ok(n) if n.is_finite() => *cell = TextChunk::raw(&format.format(n)),
+                _ => {}
+            }
+        }
+        if let Some(spec) = self.columns.get_mut(col) {
+            *spec = ColumnSpec::Right;
+        }
+        self
+    }
+}
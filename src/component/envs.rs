@@ -1,4 +1,8 @@
-use std::fmt::Display;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, Write},
+};
 
 use crate::prelude::*;
 
@@ -6,22 +10,22 @@ use crate::prelude::*;
 /// Halfway through implementing arguments, stay tuned.
 #[derive(Debug, Clone)]
 pub struct Environment {
-    name: String,
-    components: Vec<Component>,
+    pub(crate) name: String,
+    pub(crate) components: Vec<Component>,
     opt: Vec<String>,
 }
 impl AsLatex for Environment {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        let opts = self.opt.join(", ");
-        format!(
-            "\\begin{{{}}}[{}] \n {} \n \\end{{{}}} \n ",
-            self.name, opts, comps, self.name
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(
+            w,
+            "\\begin{{{}}}{} \n ",
+            self.name,
+            bracketed(&self.opt, ", ")
+        )?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n \\end{{{}}} \n ", self.name)
     }
 }
 impl Populate for Environment {
@@ -40,7 +44,7 @@ impl Populate for Environment {
 }
 impl Opt for Environment {
     fn add_option(&mut self, opt: &str) {
-        self.opt.push(opt.to_string());
+        push_option(&mut self.opt, opt);
     }
 }
 impl Environment {
@@ -56,25 +60,31 @@ impl Environment {
 /// OG List, itemize or enumerate. If y'all want description, please put up an issue.
 #[derive(Debug, Clone)]
 pub struct List {
-    items: Vec<Component>,
+    pub(crate) items: Vec<Component>,
     typ: ListType,
     opt: Vec<String>,
+    overlay: Option<Overlay>,
+    item_overlays: HashMap<usize, Overlay>,
 }
 impl AsLatex for List {
-    fn to_string(&self) -> String {
-        let comps = self
-            .items
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let opt = self
+            .overlay
             .iter()
-            .map(|s| format!("\t\\item {}\n", s.to_string()))
-            .collect::<String>();
-        let opts = self.opt.join(", ");
-        format!(
-            "\\begin{{{}}}[{}] \n {} \n \\end{{{}}} \n ",
-            self.typ.to_string(),
-            opts,
-            comps,
-            self.typ.to_string()
-        )
+            .map(|x| x.to_string())
+            .chain(self.opt.iter().cloned())
+            .collect::<Vec<_>>();
+        write!(w, "\\begin{{{}}}{} \n ", self.typ, bracketed(&opt, ", "))?;
+        for (i, item) in self.items.iter().enumerate() {
+            write!(w, "\t\\item")?;
+            if let Some(o) = self.item_overlays.get(&i) {
+                write!(w, "{}", o)?;
+            }
+            write!(w, " ")?;
+            item.write_to(w)?;
+            writeln!(w)?;
+        }
+        write!(w, " \n \\end{{{}}} \n ", self.typ)
     }
 }
 impl Populate for List {
@@ -93,16 +103,12 @@ impl Populate for List {
 }
 impl Opt for List {
     fn add_option(&mut self, opt: &str) {
-        self.opt.push(opt.to_string());
+        push_option(&mut self.opt, opt);
     }
 }
 impl List {
     pub fn new(typ: ListType) -> Self {
-        Self {
-            items: vec![],
-            typ,
-            opt: vec![],
-        }
+        Self::with_items(typ, vec![])
     }
 
     pub fn with_items(typ: ListType, items: Vec<Component>) -> Self {
@@ -110,8 +116,24 @@ impl List {
             items,
             typ,
             opt: vec![],
+            overlay: None,
+            item_overlays: HashMap::new(),
         }
     }
+
+    /// The overlay every item gets unless it has its own, usually `Overlay::incremental()` to
+    /// reveal the items one at a time.
+    pub fn set_overlay(&mut self, overlay: Overlay) -> &mut Self {
+        self.overlay = Some(overlay);
+        self
+    }
+
+    /// Attaches an item that is only shown on some slides: `\item<2-> ...`.
+    pub fn attach_overlaid(&mut self, item: Component, overlay: Overlay) -> &mut Self {
+        self.item_overlays.insert(self.items.len(), overlay);
+        self.items.push(item);
+        self
+    }
 }
 
 /// Variants for itemize and enumerate.
@@ -135,43 +157,350 @@ impl Display for ListType {
     }
 }
 
+/// Whether the caption goes above or below what it captions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptionPosition {
+    Above,
+    #[default]
+    Below,
+}
+
 #[derive(Debug, Clone)]
 pub struct Figure {
-    img: Image,
+    pub(crate) img: Image,
     caption: String,
-    opt: Vec<String>,
+    short_caption: Option<String>,
+    pub(crate) label: Option<Label>,
+    position: CaptionPosition,
+    pub(crate) placement: FloatPlacement,
 }
 
 impl Figure {
     pub fn new(img: &str, caption: String) -> Self {
-        Self {
-            img: Image::new(img),
-            caption,
-            opt: vec![],
-        }
+        Self::from_img(Image::new(img), caption)
     }
     pub fn from_img(img: Image, caption: String) -> Self {
         Self {
             img,
             caption,
-            opt: vec![],
+            short_caption: None,
+            label: None,
+            position: CaptionPosition::Below,
+            placement: FloatPlacement::default(),
+        }
+    }
+
+    /// What goes in the list of figures, instead of the full caption.
+    pub fn set_short_caption(&mut self, short: &str) -> &mut Self {
+        self.short_caption = Some(short.to_string());
+        self
+    }
+
+    /// Labels it `fig:<name>`. The label goes right after the caption, so that it picks up the
+    /// figure's number rather than the section's.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Figure(name.to_string()));
+        self
+    }
+
+    /// Below the image by default.
+    pub fn set_caption_position(&mut self, position: CaptionPosition) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    fn write_caption(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\caption")?;
+        if let Some(short) = &self.short_caption {
+            write!(w, "[{}]", short)?;
         }
+        write!(w, "{{{}}} \n ", self.caption)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the placement, then replaces whatever placement there was.
+    pub fn set_placement(&mut self, placement: &[Placement]) -> TexResult<&mut Self> {
+        self.placement.set(placement)?;
+        Ok(self)
+    }
+
+    pub fn placement(&self) -> &[Placement] {
+        self.placement.get()
+    }
+
+    pub(crate) fn packages(&self) -> Vec<Package> {
+        self.placement.package().into_iter().collect()
     }
 }
 
 impl AsLatex for Figure {
-    fn to_string(&self) -> String {
-        format!(
-            "\\begin{{figure}}[{}] \n \\centering \n {} \n \\caption{{{}}} \n \\end{{figure}} ",
-            self.opt.join(", "),
-            self.img.to_string(),
-            self.caption
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{figure}}{} \n \\centering \n ", self.placement)?;
+        if self.position == CaptionPosition::Above {
+            self.write_caption(w)?;
+        }
+        self.img.write_to(w)?;
+        write!(w, " \n ")?;
+        if self.position == CaptionPosition::Below {
+            self.write_caption(w)?;
+        }
+        write!(w, "\\end{{figure}} ")
     }
 }
 
 impl Opt for Figure {
+    /// Placement specifiers, like `ht`. Ones already there are skipped.
+    fn add_option(&mut self, opt: &str) {
+        self.placement.add(opt);
+    }
+}
+
+/// One panel of a `MultiFigure`: an image with its own caption and label.
+/// Unless it's given a width, it gets its share of the row.
+#[derive(Debug, Clone)]
+pub struct SubFigure {
+    pub(crate) img: Image,
+    caption: String,
+    pub(crate) label: Option<Label>,
+    width: Option<Length>,
+}
+impl SubFigure {
+    pub fn new(img: Image, caption: &str) -> Self {
+        Self {
+            img,
+            caption: caption.to_string(),
+            label: None,
+            width: None,
+        }
+    }
+
+    /// Labels it `fig:<name>`.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Figure(name.to_string()));
+        self
+    }
+
+    pub fn set_width(&mut self, width: Length) -> TexResult<&mut Self> {
+        if !width.is_positive() {
+            return Err(TexError::InvalidOptions(format!(
+                "subfigure width {} has to be positive",
+                width
+            )));
+        }
+        self.width = Some(width);
+        Ok(self)
+    }
+
+    fn write_to(&self, width: Length, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "\\begin{{subfigure}}[b]{{{}}}",
+            self.width.unwrap_or(width)
+        )?;
+        writeln!(w, "\\centering")?;
+        if self.img.opt.is_empty() {
+            // Fill the panel, rather than overflow it.
+            writeln!(
+                w,
+                "\\includegraphics[width=\\linewidth]{{{}}}",
+                self.img.path
+            )?;
+        } else {
+            self.img.write_to(w)?;
+        }
+        writeln!(w, "\\caption{{{}}}", self.caption)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        writeln!(w, "\\end{{subfigure}}")
+    }
+}
+
+/// A `figure` made of several `SubFigure`s (with `subcaption`), laid out `columns` to a row.
+/// Each panel gets its own letter, caption and label, on top of the figure's.
+#[derive(Debug, Clone)]
+pub struct MultiFigure {
+    pub(crate) subfigures: Vec<SubFigure>,
+    columns: usize,
+    caption: String,
+    pub(crate) label: Option<Label>,
+    pub(crate) placement: FloatPlacement,
+}
+impl MultiFigure {
+    /// `columns` is clamped to at least 1.
+    pub fn new(columns: usize, caption: &str) -> Self {
+        Self {
+            subfigures: vec![],
+            columns: columns.max(1),
+            caption: caption.to_string(),
+            label: None,
+            placement: FloatPlacement::default(),
+        }
+    }
+
+    pub fn add_subfigure(&mut self, sub: SubFigure) -> &mut Self {
+        self.subfigures.push(sub);
+        self
+    }
+
+    /// Labels it `fig:<name>`.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Figure(name.to_string()));
+        self
+    }
+
+    /// Checks the placement, then replaces whatever placement there was.
+    pub fn set_placement(&mut self, placement: &[Placement]) -> TexResult<&mut Self> {
+        self.placement.set(placement)?;
+        Ok(self)
+    }
+
+    pub fn placement(&self) -> &[Placement] {
+        self.placement.get()
+    }
+
+    pub fn subfigures(&self) -> &[SubFigure] {
+        &self.subfigures
+    }
+
+    /// A little under an even share of the line, so that `\hfill` has something to work with.
+    pub fn width(&self) -> Length {
+        Length::LineWidth((98.0 / self.columns as f64).floor() / 100.0)
+    }
+
+    /// The figure's label, then every panel's.
+    pub(crate) fn labels(&self) -> Vec<&Label> {
+        self.label
+            .iter()
+            .chain(self.subfigures.iter().filter_map(|x| x.label.as_ref()))
+            .collect()
+    }
+
+    pub(crate) fn packages(&self) -> Vec<Package> {
+        let mut out = vec![package!("graphicx"), package!("subcaption")];
+        out.extend(self.placement.package());
+        out
+    }
+}
+impl AsLatex for MultiFigure {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "\\begin{{figure}}{}", self.placement)?;
+        writeln!(w, "\\centering")?;
+        for (i, row) in self.subfigures.chunks(self.columns).enumerate() {
+            if i > 0 {
+                // A blank line ends the row.
+                writeln!(w)?;
+            }
+            for (j, sub) in row.iter().enumerate() {
+                if j > 0 {
+                    writeln!(w, "\\hfill")?;
+                }
+                sub.write_to(self.width(), w)?;
+            }
+        }
+        writeln!(w, "\\caption{{{}}}", self.caption)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        writeln!(w, "\\end{{figure}}")
+    }
+}
+impl Opt for MultiFigure {
+    /// Placement specifiers, like `ht`. Ones already there are skipped.
+    fn add_option(&mut self, opt: &str) {
+        self.placement.add(opt);
+    }
+}
+
+/// The `table` float: a `Table` with a caption and a label, so that it gets a number and shows
+/// up in the list of tables. The caption goes above the table.
+#[derive(Debug, Clone)]
+pub struct TableFloat {
+    pub(crate) table: Table,
+    caption: String,
+    short_caption: Option<String>,
+    pub(crate) label: Option<Label>,
+    centering: bool,
+    pub(crate) placement: FloatPlacement,
+}
+
+impl TableFloat {
+    pub fn new(table: Table, caption: &str) -> Self {
+        Self {
+            table,
+            caption: caption.to_string(),
+            short_caption: None,
+            label: None,
+            centering: true,
+            placement: FloatPlacement::default(),
+        }
+    }
+
+    /// What goes in the list of tables, instead of the full caption.
+    pub fn set_short_caption(&mut self, short: &str) -> &mut Self {
+        self.short_caption = Some(short.to_string());
+        self
+    }
+
+    /// Labels it `tab:<name>`.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Table(name.to_string()));
+        self
+    }
+
+    /// On by default.
+    pub fn set_centering(&mut self, centering: bool) -> &mut Self {
+        self.centering = centering;
+        self
+    }
+
+    /// Checks the placement, then replaces whatever placement there was.
+    pub fn set_placement(&mut self, placement: &[Placement]) -> TexResult<&mut Self> {
+        self.placement.set(placement)?;
+        Ok(self)
+    }
+
+    pub fn placement(&self) -> &[Placement] {
+        self.placement.get()
+    }
+
+    /// Including the ones the table needs.
+    pub(crate) fn packages(&self) -> Vec<Package> {
+        let mut out = self.table.packages();
+        out.extend(self.placement.package());
+        out
+    }
+
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+}
+
+impl AsLatex for TableFloat {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "\\begin{{table}}{}", self.placement)?;
+        if self.centering {
+            writeln!(w, "\\centering")?;
+        }
+        write!(w, "\\caption")?;
+        if let Some(short) = &self.short_caption {
+            write!(w, "[{}]", short)?;
+        }
+        writeln!(w, "{{{}}}", self.caption)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        self.table.write_to(w)?;
+        writeln!(w, "\n\\end{{table}}")
+    }
+}
+
+impl Opt for TableFloat {
+    /// Placement specifiers, like `ht`. Ones already there are skipped.
     fn add_option(&mut self, opt: &str) {
-        self.opt.push(opt.to_string());
+        self.placement.add(opt);
     }
 }
@@ -0,0 +1,243 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// The amsmath display environments. `equation` is plain latex, but is here for the sake of
+/// having one type for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquationKind {
+    /// One line.
+    Equation,
+    /// Any number of lines, lined up at the alignment points.
+    Align,
+    /// Any number of lines, centred.
+    Gather,
+    /// One long equation split over several lines, numbered once.
+    Multline,
+}
+impl EquationKind {
+    pub fn env(&self) -> &str {
+        match &self {
+            EquationKind::Equation => "equation",
+            EquationKind::Align => "align",
+            EquationKind::Gather => "gather",
+            EquationKind::Multline => "multline",
+        }
+    }
+}
+
+/// What a line does about its equation number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Numbering {
+    Auto,
+    /// `\nonumber`
+    NoNumber,
+    /// `\notag`
+    NoTag,
+    /// `\tag{..}`
+    Tag(String),
+}
+
+/// One line of an `Equation`. The cells are separated by alignment points (`&`), so anything
+/// other than `align` takes exactly one.
+#[derive(Debug, Clone)]
+pub struct EquationRow {
+    pub(crate) cells: Vec<Expr>,
+    pub(crate) label: Option<Label>,
+    numbering: Numbering,
+}
+impl AsLatex for EquationRow {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        for (i, c) in self.cells.iter().enumerate() {
+            if i > 0 {
+                write!(w, " & ")?;
+            }
+            c.write_to(w)?;
+        }
+        match &self.numbering {
+            Numbering::Auto => {}
+            Numbering::NoNumber => write!(w, " \\nonumber")?,
+            Numbering::NoTag => write!(w, " \\notag")?,
+            Numbering::Tag(t) => write!(w, " \\tag{{{}}}", t)?,
+        }
+        if let Some(l) = &self.label {
+            write!(w, " \\label{{{}}}", l.key())?;
+        }
+        Ok(())
+    }
+}
+impl EquationRow {
+    /// Whether this line gets a number (or a tag) to refer to, in an environment that's
+    /// `numbered` or not.
+    fn has_number(&self, numbered: bool) -> bool {
+        match self.numbering {
+            Numbering::Tag(_) => true,
+            Numbering::NoNumber | Numbering::NoTag => false,
+            Numbering::Auto => numbered,
+        }
+    }
+
+    pub fn new(cells: Vec<Expr>) -> Self {
+        Self {
+            cells,
+            label: None,
+            numbering: Numbering::Auto,
+        }
+    }
+
+    /// `lhs &= rhs`, or whichever relation, with the alignment point before it.
+    pub fn relation(lhs: Expr, op: BinOp, rhs: Expr) -> Self {
+        Self::new(vec![lhs, Expr::Raw(String::new()).rel(op, rhs)])
+    }
+
+    /// Labels that aren't `Label::Equation` are accepted, but then `\ref` will print the
+    /// equation number where you might not expect it.
+    pub fn set_label(&mut self, label: Label) -> &mut Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub fn set_numbering(&mut self, numbering: Numbering) -> &mut Self {
+        self.numbering = numbering;
+        self
+    }
+
+    pub fn nonumber(&mut self) -> &mut Self {
+        self.set_numbering(Numbering::NoNumber)
+    }
+
+    pub fn notag(&mut self) -> &mut Self {
+        self.set_numbering(Numbering::NoTag)
+    }
+}
+impl From<Expr> for EquationRow {
+    fn from(value: Expr) -> Self {
+        Self::new(vec![value])
+    }
+}
+
+/// `equation`, `align`, `gather` and `multline`, starred or not. Needs amsmath, which the
+/// `Document` adds by itself if you haven't.
+///
+/// ```rust
+/// use rust_texas::prelude::*;
+/// fn dummy() -> TexResult<()> {
+///     let x = Expr::var("x");
+///     let mut eq = Equation::new(EquationKind::Align);
+///     let mut first = EquationRow::relation(x.clone().pow(2), BinOp::Eq, x.clone() * x.clone());
+///     first.set_label(Label::Equation("square".to_string()));
+///     eq.add_row(first)?.add_row(EquationRow::relation(Expr::raw(""), BinOp::Ge, 0.into()))?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Equation {
+    kind: EquationKind,
+    numbered: bool,
+    pub(crate) rows: Vec<EquationRow>,
+}
+impl AsLatex for Equation {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let env = format!(
+            "{}{}",
+            self.kind.env(),
+            if self.numbered { "" } else { "*" }
+        );
+        writeln!(w, "\\begin{{{}}}", env)?;
+        for (i, row) in self.rows.iter().enumerate() {
+            row.write_to(w)?;
+            if i + 1 < self.rows.len() {
+                writeln!(w, " \\\\")?;
+            } else {
+                writeln!(w)?;
+            }
+        }
+        writeln!(w, "\\end{{{}}}", env)
+    }
+}
+impl Equation {
+    pub fn new(kind: EquationKind) -> Self {
+        Self {
+            kind,
+            numbered: true,
+            rows: vec![],
+        }
+    }
+
+    /// The starred version.
+    pub fn unnumbered(kind: EquationKind) -> Self {
+        Self {
+            kind,
+            numbered: false,
+            rows: vec![],
+        }
+    }
+
+    /// `equation` with just the one line.
+    pub fn single(expr: Expr) -> Self {
+        Self {
+            kind: EquationKind::Equation,
+            numbered: true,
+            rows: vec![expr.into()],
+        }
+    }
+
+    /// Fails if the row has alignment points and this isn't `align`, if this is `equation`
+    /// and already has its line, or if this is `multline` and the row is a second one with a label.
+    pub fn add_row(&mut self, row: EquationRow) -> TexResult<&mut Self> {
+        if row.cells.len() > 1 && self.kind != EquationKind::Align {
+            return Err(TexError::EquationShape(
+                self.kind.env().to_string(),
+                format!(
+                    "it has no alignment points, but the row has {} cells",
+                    row.cells.len()
+                ),
+            ));
+        }
+        if self.kind == EquationKind::Equation && !self.rows.is_empty() {
+            return Err(TexError::EquationShape(
+                self.kind.env().to_string(),
+                "it only takes one line; use gather or align".to_string(),
+            ));
+        }
+        if self.kind == EquationKind::Multline
+            && row.label.is_some()
+            && self.rows.iter().any(|x| x.label.is_some())
+        {
+            return Err(TexError::EquationShape(
+                self.kind.env().to_string(),
+                "it's numbered once, so only one row can have a label".to_string(),
+            ));
+        }
+        self.rows.push(row);
+        Ok(self)
+    }
+
+    /// Labels on lines that don't get a number: in a starred environment, or on a
+    /// `\nonumber` line.
+    pub(crate) fn check(&self) -> Vec<TexError> {
+        self.rows
+            .iter()
+            .filter(|x| !x.has_number(self.numbered))
+            .filter_map(|x| x.label.as_ref())
+            .map(|x| TexError::LabelUnnumbered(x.key()))
+            .collect()
+    }
+
+    /// amsmath, plus whatever the formulas need.
+    pub fn packages(&self) -> Vec<Package> {
+        let mut out = vec![package!("amsmath")];
+        for c in self.rows.iter().flat_map(|x| &x.cells) {
+            out.extend(c.packages());
+        }
+        out
+    }
+
+    pub fn kind(&self) -> EquationKind {
+        self.kind
+    }
+
+    pub fn rows(&self) -> &[EquationRow] {
+        &self.rows
+    }
+}
@@ -0,0 +1,48 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// Whether a formula sits in the text, `\(...\)`, or on its own line, `\[...\]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathMode {
+    Inline,
+    Display,
+}
+
+/// An `Expr` in the document. Same output as a math `TextChunk`, but the tree is kept around.
+/// `Expr`s convert into inline `Formula` components, so `doc.attach((x + 1).into())` works.
+#[derive(Debug, Clone)]
+pub struct Formula {
+    pub(crate) expr: Expr,
+    mode: MathMode,
+}
+impl AsLatex for Formula {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        match self.mode {
+            MathMode::Inline => write!(w, "\\(")?,
+            MathMode::Display => write!(w, "\\[")?,
+        }
+        self.expr.write_to(w)?;
+        match self.mode {
+            MathMode::Inline => write!(w, "\\)"),
+            MathMode::Display => write!(w, "\\]"),
+        }
+    }
+}
+impl Formula {
+    pub fn new(expr: Expr, mode: MathMode) -> Self {
+        Self { expr, mode }
+    }
+
+    pub fn inline(expr: Expr) -> Self {
+        Self::new(expr, MathMode::Inline)
+    }
+
+    pub fn display(expr: Expr) -> Self {
+        Self::new(expr, MathMode::Display)
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
@@ -1,19 +1,20 @@
+use std::io::{self, Write};
+
 use crate::prelude::*;
 
 /// \part{}: Only available for \documentclass{book}
 #[derive(Debug, Clone)]
 pub struct Part {
-    name: String,
-    components: Vec<Component>,
+    pub(crate) name: String,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Part {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!("\\part{{{}}} \n {} \n ", self.name, comps)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\part{{{}}} \n ", self.name)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n ")
     }
 }
 impl Populate for Part {
@@ -49,17 +50,16 @@ impl Part {
 /// \chapter{}: Only available for \documentclass{book}
 #[derive(Debug, Clone)]
 pub struct Chapter {
-    name: String,
-    components: Vec<Component>,
+    pub(crate) name: String,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Chapter {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!("\\chapter{{{}}} \n {} \n ", self.name, comps)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\chapter{{{}}} \n ", self.name)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n ")
     }
 }
 impl Populate for Chapter {
@@ -95,17 +95,16 @@ impl Chapter {
 /// \section{}: Major partitioning device within a document
 #[derive(Debug, Clone)]
 pub struct Section {
-    name: String,
-    components: Vec<Component>,
+    pub(crate) name: String,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Section {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!("\\section{{{}}} \n {} \n ", self.name, comps)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\section{{{}}} \n ", self.name)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n ")
     }
 }
 impl Populate for Section {
@@ -143,17 +142,16 @@ impl Section {
 /// \subsection{}
 #[derive(Debug, Clone)]
 pub struct Subsection {
-    name: String,
-    components: Vec<Component>,
+    pub(crate) name: String,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Subsection {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!("\\subsection{{{}}} \n {} \n ", self.name, comps)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\subsection{{{}}} \n ", self.name)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n ")
     }
 }
 impl Populate for Subsection {
@@ -189,16 +187,15 @@ impl Subsection {
 /// Block of text bracketed by "\n\n". Generates a latex paragraph.
 #[derive(Debug, Clone)]
 pub struct Paragraph {
-    components: Vec<Component>,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Paragraph {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        format!("\n\n {} \n\n ", comps)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\n\n ")?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        write!(w, " \n\n ")
     }
 }
 impl Populate for Paragraph {
@@ -215,6 +212,11 @@ impl Populate for Paragraph {
         Ok(self)
     }
 }
+impl Default for Paragraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl Paragraph {
     pub fn new() -> Self {
         Self { components: vec![] }
@@ -228,19 +230,20 @@ impl Paragraph {
 /// Terminated by "\\ \n", causes linebreaks within the document.
 #[derive(Debug, Clone)]
 pub struct Line {
-    components: Vec<Component>,
+    pub(crate) components: Vec<Component>,
 }
 impl AsLatex for Line {
-    fn to_string(&self) -> String {
-        let comps = self
-            .components
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<String>();
-        if comps.trim().is_empty() {
-            format!("\n")
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        // Lines are short, and we need to know whether this one is blank before writing it.
+        let mut comps = vec![];
+        for c in &self.components {
+            c.write_to(&mut comps)?;
+        }
+        if comps.iter().all(|x| x.is_ascii_whitespace()) {
+            writeln!(w)
         } else {
-            format!("{} \\\\\n", comps)
+            w.write_all(&comps)?;
+            writeln!(w, " \\\\")
         }
     }
 }
@@ -258,6 +261,11 @@ impl Populate for Line {
         Ok(self)
     }
 }
+impl Default for Line {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl Line {
     pub fn new() -> Self {
         Self { components: vec![] }
@@ -1,25 +1,27 @@
+use std::io::{self, Write};
+
 use crate::prelude::*;
 
 /// Images!
 /// Please enable images for the current document before, using: `doc.enable_graphicx(path)`
 #[derive(Debug, Clone)]
 pub struct Image {
-    path: String,
-    opt: Vec<String>,
+    pub(crate) path: String,
+    pub(crate) opt: Vec<String>,
 }
 impl AsLatex for Image {
-    fn to_string(&self) -> String {
-        let options = self
-            .opt
-            .iter()
-            .map(|s| format!("{}, ", s))
-            .collect::<String>();
-        format!("\\includegraphics[{}]{{{}}} \n", options, self.path)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "\\includegraphics{}{{{}}} ",
+            bracketed(&self.opt, ", "),
+            self.path
+        )
     }
 }
 impl Opt for Image {
     fn add_option(&mut self, opt: &str) {
-        self.opt.push(opt.to_string());
+        push_option(&mut self.opt, opt);
     }
 }
 impl Image {
@@ -30,10 +32,23 @@ impl Image {
         }
     }
 
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
     pub fn with_options(path: &str, opt: Vec<String>) -> Self {
         Self {
             path: path.to_string(),
             opt,
         }
     }
+
+    /// Checks the options, then adds them, replacing any already there for the same key.
+    pub fn set_options(&mut self, options: &ImageOptions) -> TexResult<&mut Self> {
+        options.check()?;
+        for x in options.to_vec() {
+            push_option(&mut self.opt, &x);
+        }
+        Ok(self)
+    }
 }
@@ -0,0 +1,279 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::prelude::*;
+
+/// Which package typesets the code. `Minted` needs Pygments, and the engine to be run with
+/// `-shell-escape` (which `Compiler` takes care of).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ListingBackend {
+    #[default]
+    Listings,
+    Minted,
+}
+
+/// File extension, minted's name for the language, and listings' (if it has it at all).
+const LANGUAGES: [(&str, &str, Option<&str>); 32] = [
+    ("rs", "rust", None),
+    ("py", "python", Some("Python")),
+    ("c", "c", Some("C")),
+    ("h", "c", Some("C")),
+    ("cpp", "cpp", Some("C++")),
+    ("cc", "cpp", Some("C++")),
+    ("hpp", "cpp", Some("C++")),
+    ("java", "java", Some("Java")),
+    ("js", "javascript", None),
+    ("ts", "typescript", None),
+    ("go", "go", None),
+    ("hs", "haskell", Some("Haskell")),
+    ("ml", "ocaml", Some("ML")),
+    ("rb", "ruby", Some("Ruby")),
+    ("sh", "bash", Some("bash")),
+    ("sql", "sql", Some("SQL")),
+    ("tex", "latex", Some("TeX")),
+    ("html", "html", Some("HTML")),
+    ("xml", "xml", Some("XML")),
+    ("json", "json", None),
+    ("toml", "toml", None),
+    ("yaml", "yaml", None),
+    ("yml", "yaml", None),
+    ("md", "markdown", None),
+    ("m", "matlab", Some("Matlab")),
+    ("r", "r", Some("R")),
+    ("lua", "lua", Some("Lua")),
+    ("pl", "perl", Some("Perl")),
+    ("php", "php", Some("PHP")),
+    ("scala", "scala", Some("Scala")),
+    ("kt", "kotlin", None),
+    ("swift", "swift", None),
+];
+
+/// The language of a file, going by its extension, as minted calls it.
+pub fn detect_language(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    LANGUAGES.iter().find(|x| x.0 == ext).map(|x| x.1)
+}
+
+/// Source code, typeset by `listings` or `minted`.
+/// Lines are 1-based, and always numbered as they are in the source, even when only a range of
+/// them is shown.
+#[derive(Debug, Clone)]
+pub struct CodeListing {
+    code: String,
+    language: Option<String>,
+    backend: ListingBackend,
+    line_numbers: bool,
+    range: Option<(usize, usize)>,
+    highlight: Vec<usize>,
+    caption: Option<String>,
+    pub(crate) label: Option<Label>,
+}
+impl CodeListing {
+    pub fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            language: None,
+            backend: ListingBackend::Listings,
+            line_numbers: false,
+            range: None,
+            highlight: vec![],
+            caption: None,
+            label: None,
+        }
+    }
+
+    /// Reads the file, and guesses the language from its extension.
+    pub fn from_file(path: &str) -> TexResult<Self> {
+        let mut out = Self::new(&fs::read_to_string(path)?);
+        out.language = detect_language(path).map(|x| x.to_string());
+        Ok(out)
+    }
+
+    /// Either backend's name for it; the ones in the extension table are translated.
+    pub fn set_language(&mut self, language: &str) -> &mut Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    pub fn set_backend(&mut self, backend: ListingBackend) -> &mut Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn set_line_numbers(&mut self, line_numbers: bool) -> &mut Self {
+        self.line_numbers = line_numbers;
+        self
+    }
+
+    /// Only show lines `first` to `last`, inclusive.
+    pub fn set_range(&mut self, first: usize, last: usize) -> TexResult<&mut Self> {
+        if first == 0 || first > last {
+            return Err(TexError::InvalidOptions(format!(
+                "{}-{} is not a range of lines",
+                first, last
+            )));
+        }
+        self.range = Some((first, last));
+        Ok(self)
+    }
+
+    /// Only `minted` can highlight lines.
+    pub fn set_highlight(&mut self, lines: &[usize]) -> &mut Self {
+        self.highlight = lines.to_vec();
+        self
+    }
+
+    pub fn set_caption(&mut self, caption: &str) -> &mut Self {
+        self.caption = Some(caption.to_string());
+        self
+    }
+
+    /// Labels it `lst:<name>`. Only a captioned listing gets a number, so `check` complains
+    /// about a label without a caption.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Code(name.to_string()));
+        self
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn backend(&self) -> ListingBackend {
+        self.backend
+    }
+
+    /// The language, as the backend calls it. `None` if listings doesn't know it.
+    pub fn language(&self) -> Option<&str> {
+        let lang = self.language.as_deref()?;
+        let known = LANGUAGES.iter().find(|x| {
+            x.1.eq_ignore_ascii_case(lang) || x.2.is_some_and(|y| y.eq_ignore_ascii_case(lang))
+        });
+        match (self.backend, known) {
+            (ListingBackend::Listings, Some(x)) => x.2,
+            (ListingBackend::Minted, Some(x)) => Some(x.1),
+            (_, None) => Some(lang),
+        }
+    }
+
+    /// Highlighted lines with `listings`, ranges or highlighted lines past the end of the code,
+    /// and a label without a caption, which has no number to refer to.
+    pub fn check(&self) -> Vec<TexError> {
+        let mut out = vec![];
+        if let (None, Some(l)) = (&self.caption, &self.label) {
+            out.push(TexError::InvalidOptions(format!(
+                "the listing labelled {} has no caption, so nothing to refer to",
+                l.key()
+            )));
+        }
+        let lines = self.code.lines().count();
+        if !self.highlight.is_empty() && self.backend == ListingBackend::Listings {
+            out.push(TexError::InvalidOptions(
+                "listings can't highlight lines; use ListingBackend::Minted".to_string(),
+            ));
+        }
+        if let Some((first, _)) = self.range.filter(|x| x.1 > lines) {
+            out.push(TexError::InvalidOptions(format!(
+                "the listing starting at line {} runs past its {} lines",
+                first, lines
+            )));
+        }
+        let (first, last) = self.range.unwrap_or((1, lines));
+        if let Some(l) = self.highlight.iter().find(|x| **x < first || **x > last) {
+            out.push(TexError::InvalidOptions(format!(
+                "line {} is highlighted, but only lines {}-{} are shown",
+                l, first, last
+            )));
+        }
+        out
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        match self.backend {
+            ListingBackend::Listings => vec![package!("listings")],
+            ListingBackend::Minted => vec![package!("minted")],
+        }
+    }
+
+    /// The `key=value` options, minus the caption and label.
+    fn options(&self) -> Vec<String> {
+        let mut out = vec![];
+        if self.line_numbers {
+            out.push(match self.backend {
+                ListingBackend::Listings => "numbers=left".to_string(),
+                ListingBackend::Minted => "linenos".to_string(),
+            });
+        }
+        if let Some((first, last)) = self.range {
+            out.push(format!("firstline={}", first));
+            out.push(format!("lastline={}", last));
+            out.push(format!("firstnumber={}", first));
+        }
+        if !self.highlight.is_empty() {
+            let lines = self
+                .highlight
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>();
+            out.push(format!("highlightlines={{{}}}", lines.join(",")));
+        }
+        out
+    }
+
+    fn write_listings(&self, w: &mut dyn Write) -> io::Result<()> {
+        let mut opts = self.options();
+        if let Some(lang) = self.language() {
+            opts.insert(0, format!("language={}", lang));
+        }
+        if let Some(caption) = &self.caption {
+            opts.push(format!("caption={{{}}}", caption));
+        }
+        if let Some(l) = &self.label {
+            opts.push(format!("label={{{}}}", l.key()));
+        }
+        write!(w, "\\begin{{lstlisting}}")?;
+        if !opts.is_empty() {
+            write!(w, "[{}]", opts.join(", "))?;
+        }
+        writeln!(w)?;
+        writeln!(w, "{}", self.code.trim_end_matches('\n'))?;
+        writeln!(w, "\\end{{lstlisting}}")
+    }
+
+    /// minted can't caption on its own, so a caption puts it in a `listing` float.
+    fn write_minted(&self, w: &mut dyn Write) -> io::Result<()> {
+        let float = self.caption.is_some() || self.label.is_some();
+        if float {
+            writeln!(w, "\\begin{{listing}}")?;
+        }
+        write!(w, "\\begin{{minted}}")?;
+        let opts = self.options();
+        if !opts.is_empty() {
+            write!(w, "[{}]", opts.join(", "))?;
+        }
+        writeln!(w, "{{{}}}", self.language().unwrap_or("text"))?;
+        writeln!(w, "{}", self.code.trim_end_matches('\n'))?;
+        writeln!(w, "\\end{{minted}}")?;
+        if float {
+            if let Some(caption) = &self.caption {
+                writeln!(w, "\\caption{{{}}}", caption)?;
+            }
+            if let Some(l) = &self.label {
+                l.write_to(w)?;
+            }
+            writeln!(w, "\\end{{listing}}")?;
+        }
+        Ok(())
+    }
+}
+impl AsLatex for CodeListing {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        match self.backend {
+            ListingBackend::Listings => self.write_listings(w),
+            ListingBackend::Minted => self.write_minted(w),
+        }
+    }
+}
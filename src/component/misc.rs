@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 use crate::prelude::*;
 
 /// \input{}, if you want that kinda thing. ~Personally, I've never used it.~
@@ -7,7 +9,7 @@ pub struct Input {
     name: String,
 }
 impl AsLatex for Input {
-    fn to_string(&self) -> String {
-        format!("\\input{{{}}}", self.name)
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\input{{{}}}", self.name)
     }
 }
@@ -1,15 +1,24 @@
-use crate::prelude::*;
+use std::io::{self, Write};
+
+use crate::{document::source_map, prelude::*};
 
 // Re-exports for compatibility.
+pub use algorithm::*;
 pub use beamer::*;
 pub use builtin::*;
+pub use data::*;
 pub use envs::*;
+pub use equation::*;
+pub use formula::*;
 pub use hierarchy::*;
 pub use image::*;
-// use markdown::mdast::Node;
+pub use listing::*;
 pub use misc::*;
+pub use options::*;
+pub use overlay::*;
 pub use table::*;
 pub use textchunk::*;
+pub use theorem::*;
 
 /// One of the main structs, almost everything you put into a document is a `Component`
 #[derive(Debug, Clone)]
@@ -23,39 +32,57 @@ pub enum Component {
 
     Frame(Frame),
     Block(Block),
+    Columns(Columns),
 
     Input(Input),
 
     Environment(Environment),
     List(List),
     Figure(Figure),
+    MultiFigure(MultiFigure),
+    Theorem(Theorem),
+    Proof(Proof),
+    CodeListing(CodeListing),
+    Algorithm(Algorithm),
+    Overlaid(Overlaid),
 
     TextChunk(TextChunk),
-    // #[cfg(feature = "markdown")]
-    // Markdown(MarkdownChunk),
     Command(String),
 
     /// Outside the figure environment. Sometimes useful.
     Image(Image),
 
     Table(Table),
+    TableFloat(TableFloat),
     Row(Row),
+    Cell(Cell),
 
     Builtin(Builtin),
+    Formula(Formula),
+    Equation(Equation),
 
     Label(Label),
     Reference(Reference),
+    Citation(Citation),
     // Dummy(Vec<Component>)
 }
 
+pub mod algorithm;
 pub mod beamer;
 pub mod builtin;
+pub mod data;
 pub mod envs;
+pub mod equation;
+pub mod formula;
 pub mod hierarchy;
 pub mod image;
+pub mod listing;
 pub mod misc;
+pub mod options;
+pub mod overlay;
 pub mod table;
 pub mod textchunk;
+pub mod theorem;
 
 impl Component {
     pub fn rank(&self) -> u8 {
@@ -63,19 +90,24 @@ impl Component {
             Component::Part(_) => 0,
             Component::Chapter(_) => 1,
             Component::Section(_) => 2,
-            // #[cfg(feature = "markdown")]
-            // Component::Markdown(_) => 2,
             Component::Subsection(_) => 3,
             Component::Paragraph(_) => 5,
             Component::Line(_) => 10,
             Component::Frame(_) => 4,
             Component::Block(_) => 5,
+            Component::Columns(_) => 5,
 
             Component::Input(_) => 9,
 
             Component::Environment(_) => 8,
             Component::List(_) => 7,
             Component::Figure(_) => 8,
+            Component::MultiFigure(_) => 8,
+            Component::Theorem(_) => 8,
+            Component::Proof(_) => 8,
+            Component::CodeListing(_) => 9,
+            Component::Algorithm(_) => 8,
+            Component::Overlaid(stuff) => stuff.content.rank(),
 
             Component::TextChunk(_) => 10,
 
@@ -84,40 +116,208 @@ impl Component {
             Component::Image(_) => 10,
 
             Component::Table(_) => 7,
+            Component::TableFloat(_) => 8,
 
             Component::Row(_) => 10,
+            Component::Cell(_) => 10,
 
             Component::Builtin(_) => 10,
+            Component::Formula(_) => 10,
+            Component::Equation(_) => 9,
             Component::Label(_) => 10,
             Component::Reference(_) => 10,
+            Component::Citation(_) => 10,
+        }
+    }
+
+    /// Short, human readable description, used for source maps and error messages.
+    pub fn describe(&self) -> String {
+        match &self {
+            Component::Part(stuff) => format!("Part \"{}\"", stuff.name),
+            Component::Chapter(stuff) => format!("Chapter \"{}\"", stuff.name),
+            Component::Section(stuff) => format!("Section \"{}\"", stuff.name),
+            Component::Subsection(stuff) => format!("Subsection \"{}\"", stuff.name),
+            Component::Frame(stuff) => format!("Frame \"{}\"", stuff.title),
+            Component::Block(stuff) => format!("Block \"{}\"", stuff.title),
+            Component::Columns(stuff) => format!("Columns ({})", stuff.columns.len()),
+            Component::Environment(stuff) => format!("Environment \"{}\"", stuff.name),
+            Component::Theorem(stuff) => format!("Theorem \"{}\"", stuff.kind),
+            Component::Proof(_) => "Proof".to_string(),
+            Component::CodeListing(_) => "CodeListing".to_string(),
+            Component::Algorithm(_) => "Algorithm".to_string(),
+            Component::Overlaid(stuff) => {
+                format!("{}{}", stuff.command.name(), stuff.overlay)
+            }
+            Component::Paragraph(_) => "Paragraph".to_string(),
+            Component::Line(_) => "Line".to_string(),
+            Component::Input(_) => "Input".to_string(),
+            Component::List(_) => "List".to_string(),
+            Component::Figure(_) => "Figure".to_string(),
+            Component::MultiFigure(_) => "MultiFigure".to_string(),
+            Component::TextChunk(_) => "TextChunk".to_string(),
+            Component::Command(_) => "Command".to_string(),
+            Component::Image(stuff) => format!("Image \"{}\"", stuff.path),
+            Component::Table(_) => "Table".to_string(),
+            Component::TableFloat(_) => "TableFloat".to_string(),
+            Component::Row(_) => "Row".to_string(),
+            Component::Cell(_) => "Cell".to_string(),
+            Component::Builtin(_) => "Builtin".to_string(),
+            Component::Formula(_) => "Formula".to_string(),
+            Component::Equation(stuff) => format!("Equation \"{}\"", stuff.kind().env()),
+            Component::Label(_) => "Label".to_string(),
+            Component::Reference(_) => "Reference".to_string(),
+            Component::Citation(_) => "Citation".to_string(),
+        }
+    }
+
+    /// Packages this component needs itself, not counting the ones its children need.
+    pub fn packages(&self) -> Vec<Package> {
+        match &self {
+            Component::TextChunk(t) => t.typ().package().into_iter().collect(),
+            Component::Formula(f) => f.expr.packages(),
+            Component::Equation(eq) => eq.packages(),
+            Component::Image(_) => vec![package!("graphicx")],
+            Component::Figure(f) => {
+                let mut out = vec![package!("graphicx")];
+                out.extend(f.packages());
+                out
+            }
+            Component::MultiFigure(f) => f.packages(),
+            Component::Theorem(_) | Component::Proof(_) => vec![package!("amsthm")],
+            Component::CodeListing(l) => l.packages(),
+            Component::Algorithm(a) => a.packages(),
+            Component::TableFloat(t) => t.packages(),
+            Component::Table(t) => t.packages(),
+            Component::Row(r) => r.packages(),
+            Component::Cell(c) => c.packages(),
+            _ => vec![],
+        }
+    }
+
+    /// Labels this component defines itself, not counting the ones in its children.
+    pub fn labels(&self) -> Vec<&Label> {
+        match &self {
+            Component::Label(l) => vec![l],
+            Component::Equation(eq) => eq.rows.iter().filter_map(|x| x.label.as_ref()).collect(),
+            Component::TableFloat(t) => t.label.iter().chain(t.table.labels()).collect(),
+            Component::Figure(f) => f.label.iter().collect(),
+            Component::MultiFigure(f) => f.labels(),
+            Component::Theorem(t) => t.label.iter().collect(),
+            Component::CodeListing(l) => l.label.iter().collect(),
+            Component::Algorithm(a) => a.label.iter().collect(),
+            Component::Table(t) => t.labels(),
+            _ => vec![],
+        }
+    }
+
+    /// References this component makes itself, not counting the ones in its children.
+    pub fn references(&self) -> Vec<&Reference> {
+        match &self {
+            Component::Reference(r) => vec![r],
+            _ => vec![],
+        }
+    }
+
+    /// Citations this component makes itself, not counting the ones in its children.
+    pub fn citations(&self) -> Vec<&Citation> {
+        match &self {
+            Component::Citation(c) => vec![c],
+            _ => vec![],
+        }
+    }
+
+    /// The components directly inside this one, in the order they are written out.
+    pub fn children(&self) -> Vec<&Component> {
+        match &self {
+            Component::Part(stuff) => stuff.components.iter().collect(),
+            Component::Chapter(stuff) => stuff.components.iter().collect(),
+            Component::Section(stuff) => stuff.components.iter().collect(),
+            Component::Subsection(stuff) => stuff.components.iter().collect(),
+            Component::Paragraph(stuff) => stuff.components.iter().collect(),
+            Component::Line(stuff) => stuff.components.iter().collect(),
+            Component::Frame(stuff) => stuff.components.iter().collect(),
+            Component::Block(stuff) => stuff.components.iter().collect(),
+            Component::Columns(stuff) => stuff.columns.iter().flat_map(|x| &x.components).collect(),
+            Component::Overlaid(stuff) => vec![&*stuff.content],
+            Component::Environment(stuff) => stuff.components.iter().collect(),
+            Component::Theorem(stuff) => stuff
+                .components
+                .iter()
+                .chain(stuff.proof.as_deref())
+                .collect(),
+            Component::Proof(stuff) => stuff.components.iter().collect(),
+            Component::List(stuff) => stuff.items.iter().collect(),
+            Component::Row(stuff) => stuff.cells.iter().collect(),
+            Component::Cell(stuff) => vec![&*stuff.content],
+            Component::TableFloat(stuff) => stuff.table.children(),
+            Component::Table(stuff) => stuff.children(),
+            _ => vec![],
+        }
+    }
+
+    /// Depth-first, pre-order walk over this component and everything inside it.
+    pub fn walk<'a, F: FnMut(&'a Component)>(&'a self, f: &mut F) {
+        f(self);
+        for c in self.children() {
+            c.walk(f);
+        }
+    }
+
+    /// Every image in here, including the ones inside figures.
+    pub fn images(&self) -> Vec<&Image> {
+        let mut out = vec![];
+        self.collect_images(&mut out);
+        out
+    }
+
+    fn collect_images<'a>(&'a self, out: &mut Vec<&'a Image>) {
+        match &self {
+            Component::Image(img) => out.push(img),
+            Component::Figure(fig) => out.push(&fig.img),
+            Component::MultiFigure(fig) => out.extend(fig.subfigures.iter().map(|x| &x.img)),
+            _ => {}
+        }
+        for c in self.children() {
+            c.collect_images(out);
         }
     }
 }
 impl AsLatex for Component {
-    fn to_string(&self) -> String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let _entered = source_map::enter(self);
         match &self {
-            Component::Part(stuff) => stuff.to_string(),
-            Component::Chapter(stuff) => stuff.to_string(),
-            Component::Section(stuff) => stuff.to_string(),
-            Component::Frame(stuff) => stuff.to_string(),
-            Component::Block(stuff) => stuff.to_string(),
-            Component::Paragraph(stuff) => stuff.to_string(),
-            Component::Line(stuff) => stuff.to_string(),
-            Component::Input(stuff) => stuff.to_string(),
-            Component::Environment(stuff) => stuff.to_string(),
-            Component::List(stuff) => stuff.to_string(),
-            Component::TextChunk(stuff) => stuff.to_string(),
-            Component::Command(stuff) => stuff.to_string(),
-            Component::Subsection(stuff) => stuff.to_string(),
-            Component::Image(stuff) => stuff.to_string(),
-            Component::Row(stuff) => stuff.to_string(),
-            Component::Table(stuff) => stuff.to_string(),
-            Component::Builtin(stuff) => stuff.to_string(),
-            Component::Figure(stuff) => stuff.to_string(),
-            Component::Label(stuff) => stuff.to_string(),
-            Component::Reference(stuff) => stuff.to_string(),
-            // #[cfg(feature = "markdown")]
-            // Component::Markdown(stuff) => stuff.to_string(),
+            Component::Part(stuff) => stuff.write_to(w),
+            Component::Chapter(stuff) => stuff.write_to(w),
+            Component::Section(stuff) => stuff.write_to(w),
+            Component::Frame(stuff) => stuff.write_to(w),
+            Component::Block(stuff) => stuff.write_to(w),
+            Component::Columns(stuff) => stuff.write_to(w),
+            Component::Paragraph(stuff) => stuff.write_to(w),
+            Component::Line(stuff) => stuff.write_to(w),
+            Component::Input(stuff) => stuff.write_to(w),
+            Component::Environment(stuff) => stuff.write_to(w),
+            Component::List(stuff) => stuff.write_to(w),
+            Component::TextChunk(stuff) => stuff.write_to(w),
+            Component::Command(stuff) => w.write_all(stuff.as_bytes()),
+            Component::Subsection(stuff) => stuff.write_to(w),
+            Component::Image(stuff) => stuff.write_to(w),
+            Component::Row(stuff) => stuff.write_to(w),
+            Component::Cell(stuff) => stuff.write_to(w),
+            Component::Table(stuff) => stuff.write_to(w),
+            Component::TableFloat(stuff) => stuff.write_to(w),
+            Component::Builtin(stuff) => stuff.write_to(w),
+            Component::Formula(stuff) => stuff.write_to(w),
+            Component::Equation(stuff) => stuff.write_to(w),
+            Component::Figure(stuff) => stuff.write_to(w),
+            Component::MultiFigure(stuff) => stuff.write_to(w),
+            Component::Theorem(stuff) => stuff.write_to(w),
+            Component::Proof(stuff) => stuff.write_to(w),
+            Component::CodeListing(stuff) => stuff.write_to(w),
+            Component::Algorithm(stuff) => stuff.write_to(w),
+            Component::Overlaid(stuff) => stuff.write_to(w),
+            Component::Label(stuff) => stuff.write_to(w),
+            Component::Reference(stuff) => stuff.write_to(w),
+            Component::Citation(stuff) => stuff.write_to(w),
         }
     }
 }
@@ -125,7 +325,7 @@ impl Populate for Component {
     fn attach(&mut self, other: Component) -> TexResult<&mut Self> {
         // assert!(self.rank() >= other.rank());
         if self.rank() > other.rank() {
-            return Err(TexError::RankMismatch(other.rank(), self.rank()).into());
+            return Err(TexError::RankMismatch(other.rank(), self.rank()));
         }
         match self {
             Component::Part(stuff) => {
@@ -155,6 +355,12 @@ impl Populate for Component {
             Component::Environment(stuff) => {
                 stuff.attach(other)?;
             }
+            Component::Theorem(stuff) => {
+                stuff.attach(other)?;
+            }
+            Component::Proof(stuff) => {
+                stuff.attach(other)?;
+            }
             Component::List(stuff) => {
                 stuff.attach(other)?;
             }
@@ -168,7 +374,7 @@ impl Populate for Component {
                 stuff.attach(other)?;
             }
             _ => {
-                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)).into());
+                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)));
             }
         };
 
@@ -181,7 +387,7 @@ impl Populate for Component {
             // dbg!(self.rank());
             // dbg!(q);
 
-            return Err(TexError::RankMismatch(q, self.rank()).into());
+            return Err(TexError::RankMismatch(q, self.rank()));
         }
         match self {
             Component::Part(stuff) => {
@@ -208,6 +414,12 @@ impl Populate for Component {
             Component::Environment(stuff) => {
                 stuff.attach_vec(other)?;
             }
+            Component::Theorem(stuff) => {
+                stuff.attach_vec(other)?;
+            }
+            Component::Proof(stuff) => {
+                stuff.attach_vec(other)?;
+            }
             Component::List(stuff) => {
                 stuff.attach_vec(other)?;
             }
@@ -224,7 +436,7 @@ impl Populate for Component {
                 stuff.attach_vec(other)?;
             }
             _ => {
-                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)).into());
+                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)));
             }
         };
 
@@ -240,8 +452,7 @@ impl Populate for Component {
             return Err(TexError::RankMismatch(
                 other.max_by_key(|x| x.rank()).unwrap().rank(),
                 self.rank(),
-            )
-            .into());
+            ));
         }
         match self {
             Component::Part(stuff) => {
@@ -268,6 +479,12 @@ impl Populate for Component {
             Component::Environment(stuff) => {
                 stuff.attach_iter(other)?;
             }
+            Component::Theorem(stuff) => {
+                stuff.attach_iter(other)?;
+            }
+            Component::Proof(stuff) => {
+                stuff.attach_iter(other)?;
+            }
             Component::List(stuff) => {
                 stuff.attach_iter(other)?;
             }
@@ -284,7 +501,7 @@ impl Populate for Component {
                 stuff.attach_iter(other)?;
             }
             _ => {
-                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)).into());
+                return Err(TexError::TraitUnimplemented(format!("{:?}", &self)));
             }
         };
 
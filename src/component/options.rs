@@ -0,0 +1,363 @@
+use std::fmt::Display;
+
+use crate::prelude::*;
+
+/// Where a float (`figure`, `table`) may go. `Exact` (`H`) pins it in place, needs the `float`
+/// package, and can't be combined with anything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Here,
+    Top,
+    Bottom,
+    Page,
+    /// `!`: ignore most of LaTeX's rules about how full a page may get.
+    Force,
+    Exact,
+}
+impl Placement {
+    pub fn spec(&self) -> char {
+        match &self {
+            Placement::Here => 'h',
+            Placement::Top => 't',
+            Placement::Bottom => 'b',
+            Placement::Page => 'p',
+            Placement::Force => '!',
+            Placement::Exact => 'H',
+        }
+    }
+
+    fn from_spec(c: char) -> Option<Placement> {
+        match c {
+            'h' => Some(Placement::Here),
+            't' => Some(Placement::Top),
+            'b' => Some(Placement::Bottom),
+            'p' => Some(Placement::Page),
+            '!' => Some(Placement::Force),
+            'H' => Some(Placement::Exact),
+            _ => None,
+        }
+    }
+
+    /// Reads a placement specifier like `ht!`. Whitespace is skipped.
+    pub fn parse(spec: &str) -> TexResult<Vec<Placement>> {
+        let mut out = vec![];
+        for c in spec.chars().filter(|x| !x.is_whitespace()) {
+            match Self::from_spec(c) {
+                Some(p) => out.push(p),
+                None => {
+                    return Err(TexError::InvalidOptions(format!(
+                        "Unknown placement {} in {}",
+                        c, spec
+                    )))
+                }
+            }
+        }
+        Self::check(&out)?;
+        Ok(out)
+    }
+
+    /// `H` has to be alone, `!` needs something to apply to, and nothing can be given twice.
+    pub fn check(placement: &[Placement]) -> TexResult<()> {
+        let spec = Self::write(placement);
+        if placement.contains(&Placement::Exact) && placement.len() > 1 {
+            return Err(TexError::InvalidOptions(format!(
+                "H can't be combined with anything else, in {}",
+                spec
+            )));
+        }
+        if placement.iter().all(|x| *x == Placement::Force) && !placement.is_empty() {
+            return Err(TexError::InvalidOptions(format!(
+                "! needs a position to go with it, in {}",
+                spec
+            )));
+        }
+        if let Some((_, p)) = placement
+            .iter()
+            .enumerate()
+            .find(|(i, x)| placement[..*i].contains(x))
+        {
+            return Err(TexError::InvalidOptions(format!(
+                "{} is given twice in {}",
+                p.spec(),
+                spec
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write(placement: &[Placement]) -> String {
+        placement.iter().map(|x| x.spec()).collect()
+    }
+
+    pub fn package(&self) -> Option<Package> {
+        match &self {
+            Placement::Exact => Some(package!("float")),
+            _ => None,
+        }
+    }
+}
+
+/// The placement of a float (`Figure`, `MultiFigure`, `TableFloat`). `set` is checked up front;
+/// `add`, which is what `Opt::add_option` does for floats, can't fail, so what it can't make
+/// sense of waits for `Document::validate`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FloatPlacement {
+    placement: Vec<Placement>,
+    unknown: Vec<String>,
+}
+impl FloatPlacement {
+    pub(crate) fn set(&mut self, placement: &[Placement]) -> TexResult<()> {
+        Placement::check(placement)?;
+        self.placement = placement.to_vec();
+        self.unknown.clear();
+        Ok(())
+    }
+
+    /// Adds the specifiers in `spec` that aren't there yet.
+    pub(crate) fn add(&mut self, spec: &str) {
+        let parsed = spec
+            .chars()
+            .filter(|x| !x.is_whitespace())
+            .map(Placement::from_spec)
+            .collect::<Option<Vec<_>>>();
+        match parsed {
+            Some(parsed) => {
+                for p in parsed {
+                    if !self.placement.contains(&p) {
+                        self.placement.push(p);
+                    }
+                }
+            }
+            None => self.unknown.push(spec.to_string()),
+        }
+    }
+
+    pub(crate) fn get(&self) -> &[Placement] {
+        &self.placement
+    }
+
+    pub(crate) fn check(&self) -> Vec<TexError> {
+        let mut out = self
+            .unknown
+            .iter()
+            .map(|x| TexError::InvalidOptions(format!("{} isn't a placement", x)))
+            .collect::<Vec<_>>();
+        out.extend(Placement::check(&self.placement).err());
+        out
+    }
+
+    /// `float`, for `H`.
+    pub(crate) fn package(&self) -> Option<Package> {
+        self.placement.iter().find_map(|x| x.package())
+    }
+}
+impl Display for FloatPlacement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.placement.is_empty() {
+            Ok(())
+        } else {
+            write!(f, "[{}]", Placement::write(&self.placement))
+        }
+    }
+}
+
+/// A TeX length. The relative ones are fractions, so `TextWidth(0.5)` is `0.5\textwidth`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Pt(f64),
+    Mm(f64),
+    Cm(f64),
+    In(f64),
+    Em(f64),
+    Ex(f64),
+    TextWidth(f64),
+    LineWidth(f64),
+    TextHeight(f64),
+}
+impl Length {
+    /// Zero or negative, NaN and infinite lengths can't size anything.
+    pub(crate) fn is_positive(&self) -> bool {
+        self.value() > 0.0 && self.value().is_finite()
+    }
+
+    fn value(&self) -> f64 {
+        match *self {
+            Length::Pt(x)
+            | Length::Mm(x)
+            | Length::Cm(x)
+            | Length::In(x)
+            | Length::Em(x)
+            | Length::Ex(x)
+            | Length::TextWidth(x)
+            | Length::LineWidth(x)
+            | Length::TextHeight(x) => x,
+        }
+    }
+}
+impl Display for Length {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (x, unit) = match *self {
+            Length::Pt(x) => (x, "pt"),
+            Length::Mm(x) => (x, "mm"),
+            Length::Cm(x) => (x, "cm"),
+            Length::In(x) => (x, "in"),
+            Length::Em(x) => (x, "em"),
+            Length::Ex(x) => (x, "ex"),
+            Length::TextWidth(1.0) => return write!(f, "\\textwidth"),
+            Length::LineWidth(1.0) => return write!(f, "\\linewidth"),
+            Length::TextHeight(1.0) => return write!(f, "\\textheight"),
+            Length::TextWidth(x) => (x, "\\textwidth"),
+            Length::LineWidth(x) => (x, "\\linewidth"),
+            Length::TextHeight(x) => (x, "\\textheight"),
+        };
+        write!(f, "{}{}", x, unit)
+    }
+}
+
+/// Options for `\includegraphics`.
+/// `scale` sizes the image on its own, so it can't be given together with `width` or `height`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageOptions {
+    width: Option<Length>,
+    height: Option<Length>,
+    scale: Option<f64>,
+    angle: Option<f64>,
+    /// Left, bottom, right, top.
+    trim: Option<[Length; 4]>,
+    clip: bool,
+    keep_aspect_ratio: bool,
+}
+impl ImageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn width(&mut self, width: Length) -> &mut Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn height(&mut self, height: Length) -> &mut Self {
+        self.height = Some(height);
+        self
+    }
+
+    pub fn scale(&mut self, scale: f64) -> &mut Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// In degrees, counterclockwise.
+    pub fn angle(&mut self, angle: f64) -> &mut Self {
+        self.angle = Some(angle);
+        self
+    }
+
+    /// How much to cut off each side. Only hidden, unless `clip` is set too.
+    pub fn trim(&mut self, left: Length, bottom: Length, right: Length, top: Length) -> &mut Self {
+        self.trim = Some([left, bottom, right, top]);
+        self
+    }
+
+    pub fn clip(&mut self, clip: bool) -> &mut Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Only makes a difference when both `width` and `height` are given.
+    pub fn keep_aspect_ratio(&mut self, keep: bool) -> &mut Self {
+        self.keep_aspect_ratio = keep;
+        self
+    }
+
+    pub fn check(&self) -> TexResult<()> {
+        if self.scale.is_some() && (self.width.is_some() || self.height.is_some()) {
+            return Err(TexError::InvalidOptions(
+                "scale can't be combined with width or height".to_string(),
+            ));
+        }
+        if self.scale.is_some_and(|x| x <= 0.0 || !x.is_finite()) {
+            return Err(TexError::InvalidOptions(
+                "scale has to be positive".to_string(),
+            ));
+        }
+        if self
+            .width
+            .iter()
+            .chain(&self.height)
+            .any(|x| !x.is_positive())
+        {
+            return Err(TexError::InvalidOptions(
+                "width and height have to be positive".to_string(),
+            ));
+        }
+        if self.angle.is_some_and(|x| !x.is_finite()) {
+            return Err(TexError::InvalidOptions(
+                "angle has to be finite".to_string(),
+            ));
+        }
+        if self.trim.iter().flatten().any(|x| !x.value().is_finite()) {
+            return Err(TexError::InvalidOptions(
+                "trim has to be finite".to_string(),
+            ));
+        }
+        if self.keep_aspect_ratio && (self.width.is_none() || self.height.is_none()) {
+            return Err(TexError::InvalidOptions(
+                "keepaspectratio needs both width and height".to_string(),
+            ));
+        }
+        if self.clip && self.trim.is_none() {
+            return Err(TexError::InvalidOptions(
+                "clip does nothing without trim".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// As `key=value` pairs, in the order graphicx applies them.
+    pub fn to_vec(&self) -> Vec<String> {
+        let mut out = vec![];
+        if let Some(trim) = &self.trim {
+            out.push(format!(
+                "trim={} {} {} {}",
+                trim[0], trim[1], trim[2], trim[3]
+            ));
+        }
+        if self.clip {
+            out.push("clip".to_string());
+        }
+        if let Some(x) = &self.angle {
+            out.push(format!("angle={}", x));
+        }
+        if let Some(x) = &self.width {
+            out.push(format!("width={}", x));
+        }
+        if let Some(x) = &self.height {
+            out.push(format!("height={}", x));
+        }
+        if self.keep_aspect_ratio {
+            out.push("keepaspectratio".to_string());
+        }
+        if let Some(x) = &self.scale {
+            out.push(format!("scale={}", x));
+        }
+        out
+    }
+}
+
+/// Adds an option to a component's list, unless it's already there. A `key=value` replaces the
+/// value given before for the same key, since LaTeX would only keep the last one anyway.
+pub(crate) fn push_option(opts: &mut Vec<String>, opt: &str) {
+    let key = opt.split('=').next().unwrap_or(opt).trim();
+    opts.retain(|x| x.split('=').next().unwrap_or(x).trim() != key);
+    opts.push(opt.to_string());
+}
+
+/// `[a, b]`, or nothing at all when there are no options.
+pub(crate) fn bracketed(opts: &[String], sep: &str) -> String {
+    if opts.is_empty() {
+        "".to_string()
+    } else {
+        format!("[{}]", opts.join(sep))
+    }
+}
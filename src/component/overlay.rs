@@ -0,0 +1,232 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::prelude::*;
+
+/// One end of a slide range: an absolute slide number, or one relative to beamer's `+` or `.`
+/// counters (`+(1)`, `.(-1)`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slide {
+    /// 1-based.
+    Number(u32),
+    /// `+`, plus the offset.
+    Next(i32),
+    /// `.`, plus the offset.
+    Current(i32),
+}
+impl Display for Slide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Slide::Number(n) => write!(f, "{}", n),
+            Slide::Next(0) => write!(f, "+"),
+            Slide::Next(k) => write!(f, "+({})", k),
+            Slide::Current(0) => write!(f, "."),
+            Slide::Current(k) => write!(f, ".({})", k),
+        }
+    }
+}
+
+/// `2`, `2-`, `-3` or `2-4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlideRange {
+    Only(Slide),
+    From(Slide),
+    Until(Slide),
+    Between(Slide, Slide),
+}
+impl Display for SlideRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            SlideRange::Only(a) => write!(f, "{}", a),
+            SlideRange::From(a) => write!(f, "{}-", a),
+            SlideRange::Until(b) => write!(f, "-{}", b),
+            SlideRange::Between(a, b) => write!(f, "{}-{}", a, b),
+        }
+    }
+}
+
+/// A beamer overlay specification, the `<2-4,6>` in `\only<2-4,6>{...}`: the slides of the frame
+/// something is shown (or uncovered, or alerted) on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlay(Vec<SlideRange>);
+impl Display for Overlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges = self.0.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        write!(f, "<{}>", ranges.join(","))
+    }
+}
+impl Overlay {
+    /// Checks each range. Absolute slides start at 1, and ranges can't run backwards.
+    pub fn new(ranges: Vec<SlideRange>) -> TexResult<Self> {
+        if ranges.is_empty() {
+            return Err(TexError::InvalidOptions(
+                "an overlay needs at least one slide".to_string(),
+            ));
+        }
+        for r in &ranges {
+            let slides = match *r {
+                SlideRange::Only(a) | SlideRange::From(a) | SlideRange::Until(a) => vec![a],
+                SlideRange::Between(a, b) => vec![a, b],
+            };
+            if slides.contains(&Slide::Number(0)) {
+                return Err(TexError::InvalidOptions(format!(
+                    "slides are numbered from 1, in <{}>",
+                    r
+                )));
+            }
+            if let SlideRange::Between(Slide::Number(a), Slide::Number(b)) = *r {
+                if a > b {
+                    return Err(TexError::InvalidOptions(format!("<{}> runs backwards", r)));
+                }
+            }
+        }
+        Ok(Self(ranges))
+    }
+
+    /// Just slide `n`.
+    pub fn only(n: u32) -> TexResult<Self> {
+        Self::new(vec![SlideRange::Only(Slide::Number(n))])
+    }
+
+    /// Slide `n` onwards.
+    pub fn from(n: u32) -> TexResult<Self> {
+        Self::new(vec![SlideRange::From(Slide::Number(n))])
+    }
+
+    /// Slides `a` to `b`, inclusive.
+    pub fn between(a: u32, b: u32) -> TexResult<Self> {
+        Self::new(vec![SlideRange::Between(
+            Slide::Number(a),
+            Slide::Number(b),
+        )])
+    }
+
+    /// `<+->`: one more slide for each thing it's given to.
+    pub fn incremental() -> Self {
+        Self(vec![SlideRange::From(Slide::Next(0))])
+    }
+
+    /// Reads a spec like `2-4,6` or `+-`, with or without the angle brackets.
+    pub fn parse(spec: &str) -> TexResult<Self> {
+        let inner = spec.trim();
+        let inner = inner
+            .strip_prefix('<')
+            .and_then(|x| x.strip_suffix('>'))
+            .unwrap_or(inner);
+        let bad = || TexError::InvalidOptions(format!("{} is not an overlay specification", spec));
+        let slide = |s: &str| -> TexResult<Slide> {
+            let s = s.trim();
+            let relative = |rest: &str| -> TexResult<i32> {
+                if rest.is_empty() {
+                    return Ok(0);
+                }
+                rest.strip_prefix('(')
+                    .and_then(|x| x.strip_suffix(')'))
+                    .and_then(|x| x.trim().parse().ok())
+                    .ok_or_else(bad)
+            };
+            if let Some(rest) = s.strip_prefix('+') {
+                Ok(Slide::Next(relative(rest)?))
+            } else if let Some(rest) = s.strip_prefix('.') {
+                Ok(Slide::Current(relative(rest)?))
+            } else {
+                s.parse().map(Slide::Number).map_err(|_| bad())
+            }
+        };
+
+        let mut ranges = vec![];
+        for part in inner.split(',') {
+            // The `-` of a negative offset is inside parentheses, so skip those.
+            let mut depth = 0;
+            let dash = part.char_indices().find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                *c == '-' && depth == 0
+            });
+            ranges.push(match dash {
+                None => SlideRange::Only(slide(part)?),
+                Some((i, _)) => {
+                    let (a, b) = (part[..i].trim(), part[i + 1..].trim());
+                    match (a.is_empty(), b.is_empty()) {
+                        (true, true) => return Err(bad()),
+                        (false, true) => SlideRange::From(slide(a)?),
+                        (true, false) => SlideRange::Until(slide(b)?),
+                        (false, false) => SlideRange::Between(slide(a)?, slide(b)?),
+                    }
+                }
+            });
+        }
+        Self::new(ranges)
+    }
+}
+
+/// What an overlay does to what it's attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayCommand {
+    /// Only there on those slides; takes no space on the others.
+    Only,
+    /// Shown on those slides, and covered (but taking space) on the others.
+    Onslide,
+    /// Like `Onslide`, but a command rather than a switch.
+    Uncover,
+    /// Like `Uncover`, but completely invisible when covered, even in transparent mode.
+    Visible,
+    Invisible,
+    /// Highlighted on those slides.
+    Alert,
+}
+impl OverlayCommand {
+    pub fn name(&self) -> &'static str {
+        match &self {
+            OverlayCommand::Only => "only",
+            OverlayCommand::Onslide => "onslide",
+            OverlayCommand::Uncover => "uncover",
+            OverlayCommand::Visible => "visible",
+            OverlayCommand::Invisible => "invisible",
+            OverlayCommand::Alert => "alert",
+        }
+    }
+}
+
+/// Any component, with an overlay: `\uncover<2->{...}`. Only makes sense inside a `Frame`, which
+/// `Document::validate` checks.
+#[derive(Debug, Clone)]
+pub struct Overlaid {
+    pub(crate) command: OverlayCommand,
+    pub(crate) overlay: Overlay,
+    pub(crate) content: Box<Component>,
+}
+impl AsLatex for Overlaid {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\{}{}{{", self.command.name(), self.overlay)?;
+        self.content.write_to(w)?;
+        writeln!(w, "}}")
+    }
+}
+impl Overlaid {
+    pub fn new(command: OverlayCommand, overlay: Overlay, content: Component) -> Self {
+        Self {
+            command,
+            overlay,
+            content: Box::new(content),
+        }
+    }
+
+    pub fn command(&self) -> OverlayCommand {
+        self.command
+    }
+
+    pub fn overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+}
+
+/// `\pause`: everything after it in the frame shows up one slide later.
+pub fn pause() -> Component {
+    Component::Command("\\pause\n".to_string())
+}
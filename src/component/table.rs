@@ -1,17 +1,191 @@
-use itertools::Itertools;
+use std::io::{self, Write};
 
 use crate::prelude::*;
 
+/// How a column is laid out. `Numeric` is siunitx's `S`, which lines numbers up on the decimal
+/// point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnSpec {
+    Left,
+    Center,
+    Right,
+    /// `p{width}`: top-aligned, wrapped to `width`.
+    Paragraph(String),
+    Numeric,
+    /// Written as-is, e.g. `|c|` for a spanning cell in a `Grid` table.
+    Raw(String),
+}
+impl ColumnSpec {
+    pub fn spec(&self) -> String {
+        match &self {
+            ColumnSpec::Left => "l".to_string(),
+            ColumnSpec::Center => "c".to_string(),
+            ColumnSpec::Right => "r".to_string(),
+            ColumnSpec::Paragraph(width) => format!("p{{{}}}", width),
+            ColumnSpec::Numeric => "S".to_string(),
+            ColumnSpec::Raw(s) => s.clone(),
+        }
+    }
+
+    /// Reads a tabular column spec like `lrp{3cm}S`. Whitespace and `|` are skipped, since the
+    /// table's style decides on vertical rules.
+    pub fn parse(spec: &str) -> TexResult<Vec<ColumnSpec>> {
+        let mut out = vec![];
+        let mut chars = spec.chars();
+        while let Some(c) = chars.next() {
+            out.push(match c {
+                'l' => ColumnSpec::Left,
+                'c' => ColumnSpec::Center,
+                'r' => ColumnSpec::Right,
+                'S' => ColumnSpec::Numeric,
+                'p' => {
+                    let rest = chars.as_str();
+                    let width = rest
+                        .strip_prefix('{')
+                        .and_then(|x| x.split_once('}'))
+                        .map(|x| x.0)
+                        .ok_or(TexError::InvalidOptions(format!(
+                            "p needs a width in {}",
+                            spec
+                        )))?;
+                    chars = rest[width.len() + 2..].chars();
+                    ColumnSpec::Paragraph(width.to_string())
+                }
+                '|' => continue,
+                c if c.is_whitespace() => continue,
+                c => {
+                    return Err(TexError::InvalidOptions(format!(
+                        "unknown column type {} in {}",
+                        c, spec
+                    )))
+                }
+            });
+        }
+        Ok(out)
+    }
+
+    pub fn package(&self) -> Option<Package> {
+        match &self {
+            ColumnSpec::Numeric => Some(package!("siunitx")),
+            _ => None,
+        }
+    }
+}
+
+/// Horizontal rules. `Top`, `Mid`, `Bottom` and `CMidRule` are booktabs.
+/// Column numbers are 1-based and inclusive, like `\cline` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    HLine,
+    Top,
+    Mid,
+    Bottom,
+    CLine(usize, usize),
+    CMidRule(usize, usize),
+}
+impl AsLatex for Rule {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        match &self {
+            Rule::HLine => write!(w, "\\hline"),
+            Rule::Top => write!(w, "\\toprule"),
+            Rule::Mid => write!(w, "\\midrule"),
+            Rule::Bottom => write!(w, "\\bottomrule"),
+            Rule::CLine(a, b) => write!(w, "\\cline{{{}-{}}}", a, b),
+            Rule::CMidRule(a, b) => write!(w, "\\cmidrule{{{}-{}}}", a, b),
+        }
+    }
+}
+impl Rule {
+    pub fn package(&self) -> Option<Package> {
+        match &self {
+            Rule::Top | Rule::Mid | Rule::Bottom | Rule::CMidRule(..) => Some(package!("booktabs")),
+            Rule::HLine | Rule::CLine(..) => None,
+        }
+    }
+}
+
+/// A table cell spanning several columns (`\multicolumn`) and/or rows (`\multirow`).
+/// The rows a cell spans into still need a cell of their own, usually an empty one.
+#[derive(Debug, Clone)]
+pub struct Cell {
+    pub(crate) content: Box<Component>,
+    pub(crate) cols: usize,
+    rows: usize,
+    align: ColumnSpec,
+}
+impl AsLatex for Cell {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        if self.cols > 1 {
+            write!(
+                w,
+                "\\multicolumn{{{}}}{{{}}}{{",
+                self.cols,
+                self.align.spec()
+            )?;
+        }
+        if self.rows > 1 {
+            write!(w, "\\multirow{{{}}}{{*}}{{", self.rows)?;
+        }
+        self.content.write_to(w)?;
+        if self.rows > 1 {
+            write!(w, "}}")?;
+        }
+        if self.cols > 1 {
+            write!(w, "}}")?;
+        }
+        Ok(())
+    }
+}
+impl Cell {
+    pub fn new(content: Component) -> Self {
+        Self {
+            content: Box::new(content),
+            cols: 1,
+            rows: 1,
+            align: ColumnSpec::Center,
+        }
+    }
+
+    pub fn span_columns(&mut self, cols: usize, align: ColumnSpec) -> &mut Self {
+        self.cols = cols;
+        self.align = align;
+        self
+    }
+
+    pub fn span_rows(&mut self, rows: usize) -> &mut Self {
+        self.rows = rows;
+        self
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        let mut out = vec![];
+        if self.rows > 1 {
+            out.push(package!("multirow"));
+        }
+        out.extend(self.align.package());
+        out
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Row {
-    cells: Vec<Component>,
+    pub(crate) cells: Vec<Component>,
+    pub(crate) rules: Vec<Rule>,
 }
 impl AsLatex for Row {
-    fn to_string(&self) -> String {
-        format!(
-            "{} \\\\ \n",
-            self.cells.iter().map(|x| x.to_string()).join(" & ")
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        for (i, cell) in self.cells.iter().enumerate() {
+            if i > 0 {
+                write!(w, " & ")?;
+            }
+            cell.write_to(w)?;
+        }
+        writeln!(w, " \\\\ ")?;
+        for r in &self.rules {
+            r.write_to(w)?;
+            writeln!(w)?;
+        }
+        Ok(())
     }
 }
 impl Populate for Row {
@@ -28,32 +202,160 @@ impl Populate for Row {
         Ok(self)
     }
 }
+impl Default for Row {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 impl Row {
     pub fn new() -> Self {
-        Self { cells: vec![] }
+        Self {
+            cells: vec![],
+            rules: vec![],
+        }
     }
 
     pub fn with_cells(cells: Vec<Component>) -> Self {
-        Self { cells }
+        Self {
+            cells,
+            rules: vec![],
+        }
+    }
+
+    /// A rule under this row, e.g. `\cline{2-3}`.
+    pub fn add_rule(&mut self, rule: Rule) -> &mut Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The number of columns this row takes up, counting spanning cells as however many
+    /// columns they span.
+    pub fn width(&self) -> usize {
+        self.cells
+            .iter()
+            .map(|x| match x {
+                Component::Cell(c) => c.cols,
+                _ => 1,
+            })
+            .sum()
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        self.rules.iter().filter_map(|x| x.package()).collect()
+    }
+}
+
+/// Which rules a table draws by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Vertical rules between all columns, `\hline` around the header and at the bottom.
+    Grid,
+    /// booktabs: `\toprule`, `\midrule` under the header, `\bottomrule`. No vertical rules.
+    Booktabs,
+    /// No rules at all, except the ones added to rows.
+    Plain,
+}
+
+impl TableStyle {
+    /// The rules above the header, under it, and at the very bottom.
+    fn rules(&self) -> [Option<Rule>; 3] {
+        match &self {
+            TableStyle::Grid => [Some(Rule::HLine); 3],
+            TableStyle::Booktabs => [Some(Rule::Top), Some(Rule::Mid), Some(Rule::Bottom)],
+            TableStyle::Plain => [None; 3],
+        }
     }
 }
+
+/// What makes a `Table` a `longtable`, one that breaks across pages. The header is repeated on
+/// every page, and every page but the last ends in the "continued" footer.
+/// A long table can't go in a `TableFloat`, so the caption and label live here instead.
+#[derive(Debug, Clone, Default)]
+pub struct LongTable {
+    caption: Option<String>,
+    pub(crate) label: Option<Label>,
+    continued: Option<String>,
+    pub(crate) last_foot: Option<Row>,
+}
+impl LongTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_caption(&mut self, caption: &str) -> &mut Self {
+        self.caption = Some(caption.to_string());
+        self
+    }
+
+    /// Labels it `tab:<name>`. Only a captioned table gets a number, so `Table::check` complains
+    /// about a label without a caption.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Table(name.to_string()));
+        self
+    }
+
+    /// Text at the bottom of every page but the last, e.g. "Continued on next page".
+    pub fn set_continued(&mut self, text: &str) -> &mut Self {
+        self.continued = Some(text.to_string());
+        self
+    }
+
+    /// A row at the very end of the table only, e.g. totals.
+    pub fn set_last_foot(&mut self, row: Row) -> &mut Self {
+        self.last_foot = Some(row);
+        self
+    }
+}
+
 /// Tables!
+/// Defaults to centred columns in the `Grid` style.
 #[derive(Debug, Clone)]
 pub struct Table {
     col: usize,
-    rows: Vec<Component>,
-    head: Row,
+    pub(crate) columns: Vec<ColumnSpec>,
+    style: TableStyle,
+    pub(crate) rows: Vec<Component>,
+    pub(crate) head: Row,
+    pub(crate) long: Option<LongTable>,
 }
 impl AsLatex for Table {
-    fn to_string(&self) -> String {
-        let s = (0..self.col).fold("|".to_string(), |acc, _x| acc + "c|");
-        let rows = self.rows.iter().map(|x| x.to_string()).collect::<String>();
-        format!(
-            "\\begin{{tabular}}{{{}}} \n \\hline \n {} \n \\hline \n {} \\hline \\end{{tabular}} ",
-            s,
-            self.head.to_string(),
-            rows
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        if let Some(long) = &self.long {
+            return self.write_long(long, w);
+        }
+        let specs = self.columns.iter().map(|x| x.spec());
+        match self.style {
+            TableStyle::Grid => {
+                let s = specs.fold("|".to_string(), |acc, x| acc + &x + "|");
+                write!(w, "\\begin{{tabular}}{{{}}} \n \\hline \n ", s)?;
+                self.head.write_to(w)?;
+                write!(w, " \n \\hline \n ")?;
+                for row in &self.rows {
+                    row.write_to(w)?;
+                }
+                write!(w, " \\hline \\end{{tabular}} ")
+            }
+            TableStyle::Booktabs | TableStyle::Plain => {
+                let booktabs = self.style == TableStyle::Booktabs;
+                writeln!(w, "\\begin{{tabular}}{{{}}}", specs.collect::<String>())?;
+                if booktabs {
+                    writeln!(w, "\\toprule")?;
+                }
+                if !self.head.cells.is_empty() {
+                    self.head.write_to(w)?;
+                    if booktabs {
+                        writeln!(w, "\\midrule")?;
+                    }
+                }
+                for row in &self.rows {
+                    row.write_to(w)?;
+                }
+                if booktabs {
+                    writeln!(w, "\\bottomrule")?;
+                }
+                writeln!(w, "\\end{{tabular}}")
+            }
+        }
     }
 }
 impl Populate for Table {
@@ -72,14 +374,176 @@ impl Populate for Table {
 }
 impl Table {
     pub fn new(col: usize, head: Row) -> Self {
+        Self::with_rows(col, head, vec![])
+    }
+
+    pub fn with_rows(col: usize, head: Row, rows: Vec<Component>) -> Self {
         Self {
             col,
+            columns: vec![ColumnSpec::Center; col],
+            style: TableStyle::Grid,
+            rows,
+            head,
+            long: None,
+        }
+    }
+
+    /// One column per spec.
+    pub fn with_columns(columns: Vec<ColumnSpec>, head: Row) -> Self {
+        Self {
+            col: columns.len(),
+            columns,
+            style: TableStyle::Grid,
             rows: vec![],
             head,
+            long: None,
         }
     }
 
-    pub fn with_rows(col: usize, head: Row, rows: Vec<Component>) -> Self {
-        Self { col, rows, head }
+    /// Turns this into a `longtable`.
+    pub fn set_long(&mut self, long: LongTable) -> &mut Self {
+        self.long = Some(long);
+        self
+    }
+
+    pub fn set_style(&mut self, style: TableStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    /// Fails if the number of specs doesn't match the number of columns.
+    pub fn set_columns(&mut self, columns: Vec<ColumnSpec>) -> TexResult<&mut Self> {
+        if columns.len() != self.col {
+            return Err(TexError::ColumnCount(self.col, columns.len()));
+        }
+        self.columns = columns;
+        Ok(self)
+    }
+
+    /// The head's cells, the rows, and the cells of the long table's last foot, if it has one.
+    pub(crate) fn children(&self) -> Vec<&Component> {
+        let foot = self.long.iter().filter_map(|x| x.last_foot.as_ref());
+        self.head
+            .cells
+            .iter()
+            .chain(&self.rows)
+            .chain(foot.flat_map(|x| &x.cells))
+            .collect()
+    }
+
+    pub(crate) fn labels(&self) -> Vec<&Label> {
+        self.long.iter().filter_map(|x| x.label.as_ref()).collect()
+    }
+
+    pub fn columns(&self) -> &[ColumnSpec] {
+        &self.columns
+    }
+
+    pub fn style(&self) -> TableStyle {
+        self.style
+    }
+
+    fn write_long(&self, long: &LongTable, w: &mut dyn Write) -> io::Result<()> {
+        let [top, mid, bottom] = self.style.rules();
+        let rule = |r: Option<Rule>, w: &mut dyn Write| -> io::Result<()> {
+            if let Some(r) = r {
+                r.write_to(w)?;
+                writeln!(w)?;
+            }
+            Ok(())
+        };
+        let specs = self.columns.iter().map(|x| x.spec());
+        let specs = match self.style {
+            TableStyle::Grid => specs.fold("|".to_string(), |acc, x| acc + &x + "|"),
+            _ => specs.collect(),
+        };
+
+        writeln!(w, "\\begin{{longtable}}{{{}}}", specs)?;
+        if let Some(caption) = &long.caption {
+            write!(w, "\\caption{{{}}}", caption)?;
+            if let Some(l) = &long.label {
+                write!(w, "\\label{{{}}}", l.key())?;
+            }
+            writeln!(w, " \\\\")?;
+        }
+        // Like the tabular ones, no header row (and no rule under it) when the header is empty.
+        let head = |w: &mut dyn Write| -> io::Result<()> {
+            rule(top, w)?;
+            if !self.head.cells.is_empty() {
+                self.head.write_to(w)?;
+                rule(mid, w)?;
+            }
+            Ok(())
+        };
+        head(w)?;
+        writeln!(w, "\\endfirsthead")?;
+
+        if long.caption.is_some() {
+            writeln!(w, "\\caption[]{{(continued)}} \\\\",)?;
+        }
+        head(w)?;
+        writeln!(w, "\\endhead")?;
+
+        rule(mid, w)?;
+        if let Some(text) = &long.continued {
+            writeln!(w, "\\multicolumn{{{}}}{{r}}{{{}}} \\\\", self.col, text)?;
+        }
+        writeln!(w, "\\endfoot")?;
+
+        if let Some(foot) = &long.last_foot {
+            rule(mid, w)?;
+            foot.write_to(w)?;
+        }
+        rule(bottom, w)?;
+        writeln!(w, "\\endlastfoot")?;
+
+        for row in &self.rows {
+            row.write_to(w)?;
+        }
+        writeln!(w, "\\end{{longtable}}")
+    }
+
+    /// Every row (the header is row 0) that isn't exactly as wide as the table.
+    /// Anything attached to the table that isn't a `Row` is skipped. A long table's last footer
+    /// comes after every other row. Also a long table with a label but no caption, since the label
+    /// would have no number to refer to.
+    pub fn check(&self) -> Vec<TexError> {
+        let foot = self.long.iter().filter_map(|x| x.last_foot.as_ref());
+        let rows = std::iter::once(&self.head)
+            .chain(self.rows.iter().filter_map(|x| match x {
+                Component::Row(r) => Some(r),
+                _ => None,
+            }))
+            .chain(foot);
+        let mut out = rows
+            .enumerate()
+            .filter(|(i, r)| !(*i == 0 && r.cells.is_empty()) && r.width() != self.col)
+            .map(|(i, r)| TexError::TableWidth(i, self.col, r.width()))
+            .collect::<Vec<_>>();
+        if let Some(LongTable {
+            caption: None,
+            label: Some(l),
+            ..
+        }) = &self.long
+        {
+            out.push(TexError::InvalidOptions(format!(
+                "the long table labelled {} has no caption, so nothing to refer to",
+                l.key()
+            )));
+        }
+        out
+    }
+
+    pub fn packages(&self) -> Vec<Package> {
+        let mut out = self.head.packages();
+        if let Some(long) = &self.long {
+            out.push(package!("longtable"));
+            out.extend(long.last_foot.iter().flat_map(|x| x.packages()));
+        }
+        if self.style == TableStyle::Booktabs {
+            out.push(package!("booktabs"));
+        }
+        out.extend(self.columns.iter().filter_map(|x| x.package()));
+        out
     }
 }
@@ -1,18 +1,13 @@
 use std::{
-    fs::{read_to_string, File},
-    io::{self, Read},
+    fs::File,
+    io::{self, Read, Write},
 };
 
-// use markdown::mdast::Node;
-// #[cfg(feature = "markdown")]
-// use markdown::{to_mdast, ParseOptions};
-
 use crate::prelude::*;
 
 /// Italics and stuff. Also includes the mathy \\(..\\) and \\[...\\], as well as the Scope variant, \\{...\\}
 /// If y'all want more (like \textbb{}, \texttt{}, etc.) please put up an issue.
-/// A few fonts come from packages, which I'm not handling.
-/// You'll get a latex error if you don't also include the package.
+/// A few fonts come from packages. The `Document` loads those for you (see `TextType::package`).
 #[derive(Debug, Clone)]
 pub enum TextType {
     Normal,
@@ -31,30 +26,42 @@ pub enum TextType {
     Strikethrough,
 }
 
+impl TextType {
+    /// The package this needs, if any. Strikethrough comes from ulem, which is loaded with
+    /// `normalem` so that it leaves \emph alone.
+    pub fn package(&self) -> Option<Package> {
+        match &self {
+            TextType::MathBb => Some(package!("amssymb")),
+            TextType::Strikethrough => Some(package!("ulem", "normalem")),
+            _ => None,
+        }
+    }
+}
+
 /// Basic text struct. Typically, a `Paragraph` or `Line` contains a bunch of these.
 /// Can also read from a file, in which case `typ` will be Normal.
 #[derive(Debug, Clone)]
 pub struct TextChunk {
     body: String,
-    typ: TextType,
+    pub(crate) typ: TextType,
 }
 impl AsLatex for TextChunk {
-    fn to_string(&self) -> String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
         match &self.typ {
-            TextType::Normal => format!("{} ", self.body),
-            TextType::Italic => format!("\\textit{{{}}} ", self.body),
-            TextType::Bold => format!("\\textbf{{{}}} ", self.body),
-            TextType::Teletype => format!("\\texttt{{{}}} ", self.body),
-            TextType::MathBold => format!("\\mathbf{{{}}} ", self.body),
-            TextType::MathCal => format!("\\mathcal{{{}}} ", self.body),
-            TextType::MathBb => format!("\\mathbb{{{}}} ", self.body),
-            TextType::MathRm => format!("\\mathrm{{{}}} ", self.body),
-            TextType::Underlined => format!("\\underline{{{}}} ", self.body),
-            TextType::InlineMath => format!("\\({}\\)", self.body),
-            TextType::DisplayMath => format!("\\[{}\\]", self.body),
-            TextType::Scope => format!("\\{{{}\\}}", self.body),
-            TextType::Verbatim => format!("\\verb|{}|", self.body),
-            TextType::Strikethrough => format!("\\sout{{{}}} ", self.body),
+            TextType::Normal => write!(w, "{} ", self.body),
+            TextType::Italic => write!(w, "\\textit{{{}}} ", self.body),
+            TextType::Bold => write!(w, "\\textbf{{{}}} ", self.body),
+            TextType::Teletype => write!(w, "\\texttt{{{}}} ", self.body),
+            TextType::MathBold => write!(w, "\\mathbf{{{}}} ", self.body),
+            TextType::MathCal => write!(w, "\\mathcal{{{}}} ", self.body),
+            TextType::MathBb => write!(w, "\\mathbb{{{}}} ", self.body),
+            TextType::MathRm => write!(w, "\\mathrm{{{}}} ", self.body),
+            TextType::Underlined => write!(w, "\\underline{{{}}} ", self.body),
+            TextType::InlineMath => write!(w, "\\({}\\)", self.body),
+            TextType::DisplayMath => write!(w, "\\[{}\\]", self.body),
+            TextType::Scope => write!(w, "\\{{{}\\}}", self.body),
+            TextType::Verbatim => write!(w, "\\verb|{}|", self.body),
+            TextType::Strikethrough => write!(w, "\\sout{{{}}} ", self.body),
         }
     }
 }
@@ -64,7 +71,7 @@ impl Populate for TextChunk {
             self.body.push_str(&ch.body);
             Ok(self)
         } else {
-            Err(TexError::RankMismatch(other.rank(), 10).into())
+            Err(TexError::RankMismatch(other.rank(), 10))
         }
     }
     fn attach_vec(&mut self, other: Vec<Component>) -> TexResult<&mut Self> {
@@ -76,7 +83,7 @@ impl Populate for TextChunk {
             if let Component::TextChunk(ch) = c {
                 self.body.push_str(&ch.body);
             } else {
-                return Err(TexError::RankMismatch(c.rank(), 10).into());
+                return Err(TexError::RankMismatch(c.rank(), 10));
             }
         }
 
@@ -114,6 +121,14 @@ impl TextChunk {
             typ,
         })
     }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn typ(&self) -> &TextType {
+        &self.typ
+    }
+
     pub fn set_type(&mut self, typ: TextType) {
         self.typ = typ;
     }
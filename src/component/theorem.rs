@@ -0,0 +1,241 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// amsthm's `\theoremstyle`s: italic body, upright body, and upright body with an italic head.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TheoremStyle {
+    #[default]
+    Plain,
+    Definition,
+    Remark,
+}
+impl TheoremStyle {
+    pub fn name(&self) -> &'static str {
+        match &self {
+            TheoremStyle::Plain => "plain",
+            TheoremStyle::Definition => "definition",
+            TheoremStyle::Remark => "remark",
+        }
+    }
+}
+
+/// How a kind of theorem is numbered.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TheoremCounter {
+    /// Its own counter, running through the whole document.
+    #[default]
+    Own,
+    /// The counter of another kind, so that lemma 2 follows theorem 1.
+    SharedWith(String),
+    /// Its own counter, reset by another one, e.g. `section` for theorem 3.1.
+    Within(String),
+    /// `\newtheorem*`.
+    Unnumbered,
+}
+
+/// The kinds of theorem beamer declares itself. They can be used in beamer documents without
+/// being declared, and declaring one replaces beamer's.
+pub(crate) const BEAMER_THEOREMS: [&str; 10] = [
+    "theorem",
+    "corollary",
+    "fact",
+    "lemma",
+    "problem",
+    "solution",
+    "definition",
+    "definitions",
+    "example",
+    "examples",
+];
+
+/// A kind of theorem, declared with `\newtheorem`. Has to be declared in the `Document`
+/// (`Document::new_theorem`) before it can be used, except for the ones beamer has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TheoremKind {
+    /// The environment, e.g. `lemma`.
+    pub name: String,
+    /// What's printed, e.g. `Lemma`.
+    pub title: String,
+    pub style: TheoremStyle,
+    pub counter: TheoremCounter,
+}
+impl TheoremKind {
+    pub fn new(name: &str, title: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            title: title.to_string(),
+            style: TheoremStyle::Plain,
+            counter: TheoremCounter::Own,
+        }
+    }
+
+    /// The usual set: theorem, lemma and corollary (sharing a counter), definition and remark.
+    pub fn defaults() -> Vec<TheoremKind> {
+        let mut out = vec![TheoremKind::new("theorem", "Theorem")];
+        for (name, title) in [("lemma", "Lemma"), ("corollary", "Corollary")] {
+            let mut k = TheoremKind::new(name, title);
+            k.set_counter(TheoremCounter::SharedWith("theorem".to_string()));
+            out.push(k);
+        }
+        let mut definition = TheoremKind::new("definition", "Definition");
+        definition.set_style(TheoremStyle::Definition);
+        let mut remark = TheoremKind::new("remark", "Remark");
+        remark.set_style(TheoremStyle::Remark);
+        out.push(definition);
+        out.push(remark);
+        out
+    }
+
+    pub fn set_style(&mut self, style: TheoremStyle) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    pub fn set_counter(&mut self, counter: TheoremCounter) -> &mut Self {
+        self.counter = counter;
+        self
+    }
+
+    /// \\newtheorem
+    pub fn declare(&self) -> String {
+        match &self.counter {
+            TheoremCounter::Own => format!("\\newtheorem{{{}}}{{{}}}", self.name, self.title),
+            TheoremCounter::SharedWith(other) => {
+                format!("\\newtheorem{{{}}}[{}]{{{}}}", self.name, other, self.title)
+            }
+            TheoremCounter::Within(parent) => format!(
+                "\\newtheorem{{{}}}{{{}}}[{}]",
+                self.name, self.title, parent
+            ),
+            TheoremCounter::Unnumbered => {
+                format!("\\newtheorem*{{{}}}{{{}}}", self.name, self.title)
+            }
+        }
+    }
+}
+
+/// An amsthm `proof`, ending in a QED symbol. Usually attached to a `Theorem`, but it can stand
+/// on its own too.
+#[derive(Debug, Clone, Default)]
+pub struct Proof {
+    title: Option<String>,
+    pub(crate) components: Vec<Component>,
+}
+impl AsLatex for Proof {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{proof}}")?;
+        if let Some(title) = &self.title {
+            write!(w, "[{}]", title)?;
+        }
+        writeln!(w)?;
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        writeln!(w, "\n\\end{{proof}}")
+    }
+}
+impl Populate for Proof {
+    fn attach(&mut self, other: Component) -> TexResult<&mut Self> {
+        self.components.push(other);
+        Ok(self)
+    }
+    fn attach_vec(&mut self, other: Vec<Component>) -> TexResult<&mut Self> {
+        self.attach_iter(other.into_iter())
+    }
+
+    fn attach_iter<I: Iterator<Item = Component>>(&mut self, other: I) -> TexResult<&mut Self> {
+        self.components.extend(other);
+        Ok(self)
+    }
+}
+impl Proof {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces "Proof", e.g. "Proof of Theorem 2".
+    pub fn set_title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_string());
+        self
+    }
+}
+
+/// A theorem, lemma, definition, ... of a kind declared in the document, with an optional title
+/// (`\begin{theorem}[Fermat]`), label and proof.
+#[derive(Debug, Clone)]
+pub struct Theorem {
+    pub(crate) kind: String,
+    title: Option<String>,
+    pub(crate) label: Option<Label>,
+    pub(crate) components: Vec<Component>,
+    /// Always a `Component::Proof`.
+    pub(crate) proof: Option<Box<Component>>,
+}
+impl AsLatex for Theorem {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "\\begin{{{}}}", self.kind)?;
+        if let Some(title) = &self.title {
+            write!(w, "[{}]", title)?;
+        }
+        writeln!(w)?;
+        if let Some(l) = &self.label {
+            l.write_to(w)?;
+        }
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        writeln!(w, "\n\\end{{{}}}", self.kind)?;
+        if let Some(proof) = &self.proof {
+            proof.write_to(w)?;
+        }
+        Ok(())
+    }
+}
+impl Populate for Theorem {
+    fn attach(&mut self, other: Component) -> TexResult<&mut Self> {
+        self.components.push(other);
+        Ok(self)
+    }
+    fn attach_vec(&mut self, other: Vec<Component>) -> TexResult<&mut Self> {
+        self.attach_iter(other.into_iter())
+    }
+
+    fn attach_iter<I: Iterator<Item = Component>>(&mut self, other: I) -> TexResult<&mut Self> {
+        self.components.extend(other);
+        Ok(self)
+    }
+}
+impl Theorem {
+    /// Get the kind from `Document::get_theorem`, or use the `theorem!` macro.
+    pub fn new(kind: &TheoremKind) -> Self {
+        Self {
+            kind: kind.name.clone(),
+            title: None,
+            label: None,
+            components: vec![],
+            proof: None,
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) -> &mut Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    /// Labels it `thm:<name>`, whatever the kind.
+    pub fn set_label(&mut self, name: &str) -> &mut Self {
+        self.label = Some(Label::Theorem(name.to_string()));
+        self
+    }
+
+    /// Goes right after the theorem.
+    pub fn set_proof(&mut self, proof: Proof) -> &mut Self {
+        self.proof = Some(Box::new(Component::Proof(proof)));
+        self
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+}
@@ -1,5 +1,8 @@
 use crate::prelude::*;
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
 
 /// Currently, only these few types are supported.
 /// There is also nothing preventing you from putting a \part{} in a document of class "part",
@@ -63,13 +66,13 @@ impl DocumentClass {
     }
 }
 impl AsLatex for DocumentClass {
-    fn to_string(&self) -> String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
         let options = self
             .opt
             .iter()
             .map(|s| format!("{}, ", s))
             .collect::<String>();
-        format!("\\documentclass[{}]{{{}}}", options, self.typ.to_string())
+        write!(w, "\\documentclass[{}]{{{}}}", options, self.typ)
     }
 }
 impl Opt for DocumentClass {
@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 use itertools::Itertools;
 
 use crate::prelude::*;
@@ -13,7 +15,7 @@ pub struct Metadata {
     pub date: bool,
 }
 impl AsLatex for Metadata {
-    fn to_string(&self) -> String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
         let title_author = format!(
             "\\title{{{}}}\n\\author{{{}}}\n",
             self.title,
@@ -25,13 +27,15 @@ impl AsLatex for Metadata {
                 ..
             } => {
                 // todo!()
-                format!(
+                write!(
+                    w,
                     "{title_author}\n{}\n",
                     if self.date { r"\date{\today}" } else { "" },
                 )
             }
             _ => {
-                format!(
+                write!(
+                    w,
                     "{title_author}\n{}\n{}\n{}\n",
                     if self.date { r"\today" } else { "" },
                     if self.maketitle { r"\maketitle" } else { "" },
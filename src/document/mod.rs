@@ -1,14 +1,20 @@
-use std::collections::HashMap;
+use std::io::{self, Write};
 
 use crate::prelude::*;
 
 mod doc_class;
 mod metadata;
 mod package;
+mod preamble;
+mod presentation;
+pub(crate) mod source_map;
 
 pub use doc_class::*;
 pub use metadata::*;
 pub use package::*;
+pub use preamble::*;
+pub use presentation::*;
+pub use source_map::{SourceMap, Span};
 
 /// The king of the land. The `Document` type is where you start.
 /// Has macro support.
@@ -17,105 +23,186 @@ pub use package::*;
 #[derive(Debug, Clone)]
 pub struct Document {
     // document_class: DocumentClass,
-    packages: Vec<Package>,
+    preamble: Preamble,
     pub metadata: Metadata,
     components: Vec<Component>,
-    commands: HashMap<String, Command>,
     // labels: HashSet<&'a Label>,
     img: bool,
     href: bool,
     scratch: bool,
     graphics_path: Vec<String>,
+    bibliography: Option<Bibliography>,
+    presentation: Presentation,
 }
 impl AsLatex for Document {
-    fn to_string(&self) -> String {
-        let dc = self.metadata.class.to_string();
-        let pkgs = self
-            .packages
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<String>();
-        let md = if !self.scratch {
-            self.metadata.to_string()
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let beamer = self.metadata.class.typ == DocumentClassType::Beamer;
+        if beamer {
+            let mut class = self.metadata.class.clone();
+            class.opt.extend(self.presentation.class_options());
+            class.write_to(w)?;
         } else {
-            "\n".to_string()
-        };
-        let beamer_init_frames: String = if self.metadata.class.typ == DocumentClassType::Beamer {
+            self.metadata.class.write_to(w)?;
+        }
+        writeln!(w)?;
+        // Clashing options are left to `problems`; the first value given wins here.
+        for p in self.packages_lenient().0 {
+            p.write_to(w)?;
+        }
+        if let Some(bib) = &self.bibliography {
+            let fallbacks = self.citations().iter().any(|x| x.kind != CiteKind::Cite);
+            bib.write_preamble(w, fallbacks)?;
+        }
+        if beamer {
+            self.presentation.write_to(w)?;
+        }
+        writeln!(w)?;
+        for c in self.preamble.commands() {
+            writeln!(w, "{} ", c.declare())?;
+        }
+        let mut style = None;
+        for t in self.preamble.theorems() {
+            if style != Some(t.style) {
+                writeln!(w, "\\theoremstyle{{{}}}", t.style.name())?;
+                style = Some(t.style);
+            }
+            if beamer && BEAMER_THEOREMS.contains(&t.name.as_str()) {
+                // Beamer has it already, and \newtheorem won't declare it twice.
+                writeln!(w, "\\let\\{0}\\relax\\let\\end{0}\\relax", t.name)?;
+            }
+            writeln!(w, "{}", t.declare())?;
+        }
+        writeln!(w)?;
+        if !self.graphics_path.is_empty() {
+            writeln!(
+                w,
+                "\\graphicspath{{{}}} ",
+                self.graphics_path
+                    .iter()
+                    .map(|x| format!("{{{}}}, ", x))
+                    .collect::<String>()
+            )?;
+        }
+        writeln!(w, "\\begin{{document}}")?;
+        if !self.scratch {
+            self.metadata.write_to(w)?;
+        } else {
+            writeln!(w)?;
+        }
+        writeln!(w)?;
+
+        if beamer {
             // Warning: Unused result. Again, cannot n-choose-2 Component Variants.
             let title_frame = Frame::with_components("", vec![textchunk!(r"\titlepage", "normal")]);
-
-            let mut toc = "".to_string();
+            title_frame.write_to(w)?;
+            writeln!(w)?;
             if self.metadata.tableofcontents {
-                toc = Frame::with_components("", vec![textchunk!(r"\tableofcontents", "normal")])
-                    .to_string();
+                Frame::with_components("", vec![textchunk!(r"\tableofcontents", "normal")])
+                    .write_to(w)?;
             };
-
-            format!("{}\n{}\n", title_frame.to_string(), toc)
+            writeln!(w)?;
         } else {
-            "\n".to_string()
-        };
-        let body = beamer_init_frames
-            + &self
-                .components
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<String>();
-
-        let cmd = self
-            .commands
-            .iter()
-            .map(|x| format!("{} \n", x.1.declare()))
-            .collect::<String>();
-
-        let gpath = if self.graphics_path.len() > 0 {
-            format!(
-                "\\graphicspath{{{}}} \n",
-                self.graphics_path
+            writeln!(w)?;
+        }
+        for c in &self.components {
+            c.write_to(w)?;
+        }
+        if let Some(bib) = &self.bibliography {
+            let cited = self.citations();
+            bib.write_body(
+                w,
+                &cited
                     .iter()
-                    .map(|x| format!("{{{}}}, ", x))
-                    .collect::<String>()
-            )
-        } else {
-            "".to_string()
-        };
-        format!(
-            "{}\n{}\n{}\n{}\\begin{{document}}\n{}\n{}\n\\end{{document}}",
-            dc, pkgs, cmd, gpath, md, body
-        )
+                    .flat_map(|x| x.keys())
+                    .map(|x| x.as_str())
+                    .collect::<Vec<_>>(),
+            )?;
+        }
+
+        write!(w, "\n\\end{{document}}")
     }
 }
 impl Document {
     pub fn new(class: DocumentClass) -> Self {
         let mut out = Self {
             // document_class: class,
-            packages: vec![],
+            preamble: Preamble::new(),
             metadata: Metadata::new(class, "title", &["author"]),
             components: vec![],
-            commands: HashMap::new(),
             // labels: HashSet::new(),
             img: true,
             href: true,
             scratch: false,
             graphics_path: vec![".".to_string()],
+            bibliography: None,
+            presentation: Presentation::new(),
         };
         out.new_package(package!("graphicx"));
         out.new_package(package!("hyperref"));
         out
     }
 
+    /// Same output as `write_to`, but also records which lines each component ended up on.
+    pub fn write_mapped(&self, w: &mut dyn Write) -> io::Result<SourceMap> {
+        source_map::record(w, |w| self.write_to(w))
+    }
+
     pub fn get_command(&self, cmd: &str) -> TexResult<Command> {
-        match self.commands.get(cmd) {
+        match self.preamble.command(cmd) {
             Some(s) => Ok(s.clone()),
-            None => Err(TexError::Undefined.into()),
+            None => Err(TexError::Undefined),
+        }
+    }
+
+    /// Declares a kind of theorem, so that `Theorem`s of that kind can be used.
+    /// In beamer, it can share a counter with one of beamer's own kinds, and declaring one of
+    /// those replaces beamer's.
+    pub fn new_theorem(&mut self, t: TheoremKind) -> TexResult<()> {
+        if self.metadata.class.typ == DocumentClassType::Beamer {
+            self.preamble.add_theorem_with(t, &BEAMER_THEOREMS)
+        } else {
+            self.preamble.add_theorem(t)
+        }
+    }
+
+    /// Beamer documents also have the kinds beamer declares itself, like `theorem` and `example`.
+    pub fn get_theorem(&self, name: &str) -> TexResult<TheoremKind> {
+        match self.preamble.theorem(name) {
+            Some(t) => Ok(t.clone()),
+            None if self.metadata.class.typ == DocumentClassType::Beamer
+                && BEAMER_THEOREMS.contains(&name) =>
+            {
+                let mut title = name.to_string();
+                title[..1].make_ascii_uppercase();
+                Ok(TheoremKind::new(name, &title))
+            }
+            None => Err(TexError::TheoremUndefined(name.to_string())),
         }
     }
 
+    pub fn preamble(&self) -> &Preamble {
+        &self.preamble
+    }
+
+    pub fn preamble_mut(&mut self) -> &mut Preamble {
+        &mut self.preamble
+    }
+
+    /// Themes and the like. Only used if the document is of class beamer.
+    pub fn presentation(&self) -> &Presentation {
+        &self.presentation
+    }
+
+    pub fn presentation_mut(&mut self) -> &mut Presentation {
+        &mut self.presentation
+    }
+
     pub fn scratch(&mut self) {
         self.scratch = true;
     }
 
     pub fn new_command(&mut self, c: Command) {
-        self.commands.insert(c.name.clone(), c);
+        self.preamble.add_command(c);
     }
 
     pub fn new_component(&mut self, new: Component) {
@@ -128,7 +215,7 @@ impl Document {
     }
 
     pub fn new_package(&mut self, new: Package) {
-        self.packages.push(new);
+        self.preamble.add_package(new);
     }
 
     pub fn enable_graphicx(&mut self, path: &str) {
@@ -139,7 +226,7 @@ impl Document {
 
     pub fn disable_graphicx(&mut self) {
         self.img = false;
-        self.packages.retain(|x| x.name != "graphicx");
+        self.preamble.remove_package("graphicx");
         self.graphics_path = vec![];
     }
 
@@ -150,12 +237,165 @@ impl Document {
 
     pub fn disable_hyperref(&mut self) {
         self.href = false;
-        self.packages.retain(|x| x.name != "hyperref");
+        self.preamble.remove_package("hyperref");
     }
 
     pub fn push_gpath(&mut self, path: &str) {
         self.graphics_path.push(path.to_string());
     }
+
+    pub fn graphics_path(&self) -> &[String] {
+        &self.graphics_path
+    }
+
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Depth-first walk over every component in the document.
+    pub fn walk<'a, F: FnMut(&'a Component)>(&'a self, f: &mut F) {
+        for c in &self.components {
+            c.walk(f);
+        }
+    }
+
+    /// Every package the document loads: the ones added with `new_package`, then the ones the
+    /// components and the bibliography need. One per name, with their options merged, and
+    /// hyperref moved towards the end (see `Preamble`). Fails if two of them want the same option
+    /// set differently.
+    pub fn packages(&self) -> TexResult<Vec<Package>> {
+        let (out, clashes) = self.packages_lenient();
+        match clashes.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(out),
+        }
+    }
+
+    /// `packages`, keeping the first value of any clashing option, and returning the clashes
+    /// alongside.
+    fn packages_lenient(&self) -> (Vec<Package>, Vec<TexError>) {
+        let mut inferred = vec![];
+        self.walk(&mut |x| inferred.extend(x.packages()));
+        if let Some(bib) = &self.bibliography {
+            inferred.extend(bib.packages());
+        }
+        if !self.preamble.theorems().is_empty() {
+            inferred.push(package!("amsthm"));
+        }
+        if self.metadata.class.typ == DocumentClassType::Beamer {
+            inferred.extend(self.presentation.packages());
+        }
+        self.preamble.packages_lenient(inferred)
+    }
+
+    /// Every label in the document, in order.
+    pub fn labels(&self) -> Vec<&Label> {
+        let mut out = vec![];
+        self.walk(&mut |x| out.extend(x.labels()));
+        out
+    }
+
+    /// Every reference in the document, in order.
+    pub fn references(&self) -> Vec<&Reference> {
+        let mut out = vec![];
+        self.walk(&mut |x| out.extend(x.references()));
+        out
+    }
+
+    /// Every citation in the document, in order.
+    pub fn citations(&self) -> Vec<&Citation> {
+        let mut out = vec![];
+        self.walk(&mut |x| out.extend(x.citations()));
+        out
+    }
+
+    pub fn set_bibliography(&mut self, bib: Bibliography) {
+        self.bibliography = Some(bib);
+    }
+
+    pub fn bibliography(&self) -> Option<&Bibliography> {
+        self.bibliography.as_ref()
+    }
+
+    pub fn bibliography_mut(&mut self) -> Option<&mut Bibliography> {
+        self.bibliography.as_mut()
+    }
+
+    /// Everything that would make for a broken document, but that latex would only
+    /// warn about (if at all). Empty if all is well.
+    pub fn problems(&self) -> Vec<TexError> {
+        let mut out = check_labels(&self.labels(), &self.references());
+        out.extend(check_citations(
+            self.bibliography.as_ref(),
+            &self.citations(),
+        ));
+        out.extend(self.packages_lenient().1);
+        let beamer = self.metadata.class.typ == DocumentClassType::Beamer;
+        let mut algorithms = vec![];
+        let (mut overlaid, mut framed) = (0, 0);
+        self.walk(&mut |x| match x {
+            Component::Overlaid(_) => overlaid += 1,
+            Component::Frame(f) => {
+                for c in &f.components {
+                    c.walk(&mut |y| framed += matches!(y, Component::Overlaid(_)) as usize);
+                }
+            }
+            Component::Table(t) => out.extend(t.check()),
+            Component::Equation(e) => out.extend(e.check()),
+            Component::Algorithm(a) => algorithms.push(a.backend()),
+            Component::CodeListing(l) => out.extend(l.check()),
+            Component::Theorem(t)
+                if self.preamble.theorem(&t.kind).is_none()
+                    && !(beamer && BEAMER_THEOREMS.contains(&t.kind.as_str())) =>
+            {
+                out.push(TexError::TheoremUndefined(t.kind.clone()))
+            }
+            Component::Figure(f) => out.extend(f.placement.check()),
+            Component::MultiFigure(f) => out.extend(f.placement.check()),
+            Component::TableFloat(t) => {
+                out.extend(t.placement.check());
+                out.extend(t.table.check());
+                if t.table.long.is_some() {
+                    out.push(TexError::LongTableInFloat)
+                }
+            }
+            _ => {}
+        });
+        if algorithms.windows(2).any(|x| x[0] != x[1]) {
+            out.push(TexError::AlgorithmBackends);
+        }
+        if overlaid > framed {
+            out.push(TexError::OverlayOutsideFrame(overlaid - framed));
+        }
+        out
+    }
+
+    pub fn validate(&self) -> TexResult<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(TexError::Invalid(problems))
+        }
+    }
+
+    /// `write_to`, but only after `validate` passes.
+    pub fn write_checked(&self, w: &mut dyn Write) -> TexResult<()> {
+        self.validate()?;
+        self.write_to(w)?;
+        Ok(())
+    }
+
+    /// `to_string`, but only after `validate` passes.
+    pub fn to_string_checked(&self) -> TexResult<String> {
+        self.validate()?;
+        Ok(self.to_string())
+    }
+
+    /// Every image the document refers to, figures included.
+    pub fn images(&self) -> Vec<&Image> {
+        self.components.iter().flat_map(|c| c.images()).collect()
+    }
 }
 impl Opt for Document {
     fn add_option(&mut self, opt: &str) {
@@ -1,3 +1,5 @@
+use std::io::{self, Write};
+
 use crate::prelude::*;
 
 /// This here is the main reason I made this crate - other crates don't let you add options
@@ -8,13 +10,13 @@ pub struct Package {
     pub(crate) opt: Vec<String>,
 }
 impl AsLatex for Package {
-    fn to_string(&self) -> String {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
         let options = self
             .opt
             .iter()
             .map(|s| format!("{}, ", s))
             .collect::<String>();
-        format!("\\usepackage[{}]{{{}}}\n", options, self.name)
+        writeln!(w, "\\usepackage[{}]{{{}}}", options, self.name)
     }
 }
 impl Opt for Package {
@@ -29,4 +31,80 @@ impl Package {
             opt: vec![],
         }
     }
+
+    pub fn with_options(name: &str, opt: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            opt: opt.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn options(&self) -> &[String] {
+        &self.opt
+    }
+
+    /// Adds `other`'s options to this one's. Fails if the two give the same `key=value` option
+    /// different values, after adding the options that don't clash.
+    pub fn merge(&mut self, other: &Package) -> TexResult<()> {
+        match self.merge_lenient(other).into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// `merge`, but keeping this one's value of any clashing option, and returning the clashes.
+    pub(crate) fn merge_lenient(&mut self, other: &Package) -> Vec<TexError> {
+        let mut clashes = vec![];
+        for o in &other.opt {
+            if self.opt.contains(o) {
+                continue;
+            }
+            if let Some((key, _)) = o.split_once('=') {
+                let clash = self.opt.iter().find(|x| {
+                    x.split_once('=')
+                        .is_some_and(|(k, _)| k.trim() == key.trim())
+                });
+                if let Some(clash) = clash {
+                    clashes.push(TexError::PackageConflict(
+                        self.name.clone(),
+                        clash.clone(),
+                        o.clone(),
+                    ));
+                    continue;
+                }
+            }
+            self.opt.push(o.clone());
+        }
+        clashes
+    }
+}
+
+/// One `Package` per name, in the order they first show up, with the options of every duplicate
+/// merged in.
+pub fn merge_packages<I: IntoIterator<Item = Package>>(packages: I) -> TexResult<Vec<Package>> {
+    let (out, clashes) = merge_packages_lenient(packages);
+    match clashes.into_iter().next() {
+        Some(e) => Err(e),
+        None => Ok(out),
+    }
+}
+
+/// `merge_packages`, but keeping the first value of any clashing option, and returning the
+/// clashes alongside.
+pub(crate) fn merge_packages_lenient<I: IntoIterator<Item = Package>>(
+    packages: I,
+) -> (Vec<Package>, Vec<TexError>) {
+    let mut out: Vec<Package> = vec![];
+    let mut clashes = vec![];
+    for p in packages {
+        match out.iter_mut().find(|x| x.name == p.name) {
+            Some(existing) => clashes.extend(existing.merge_lenient(&p)),
+            None => out.push(p),
+        }
+    }
+    (out, clashes)
 }
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+
+use crate::prelude::*;
+
+/// Packages that have to be loaded after hyperref (see its README).
+const AFTER_HYPERREF: [&str; 6] = [
+    "cleveref",
+    "bookmark",
+    "hypcap",
+    "glossaries",
+    "glossaries-extra",
+    "amsrefs",
+];
+
+/// Where a package goes relative to the others: amsthm after amsmath (so, late), hyperref after
+/// nearly everything, and the handful of packages that patch hyperref after that. Everything else
+/// keeps its order.
+fn load_order(name: &str) -> u8 {
+    if name == "amsthm" {
+        1
+    } else if name == "hyperref" {
+        2
+    } else if AFTER_HYPERREF.contains(&name) {
+        3
+    } else {
+        0
+    }
+}
+
+/// The packages, commands and theorem kinds of a `Document`.
+///
+/// Packages are kept in the order they were added, and merged by name when the preamble is
+/// written, so adding one twice is harmless. Commands are written in the order they were added,
+/// except that a command is always declared after the commands its definition uses. Theorem kinds
+/// are declared in the order they were added, after the commands.
+#[derive(Debug, Clone, Default)]
+pub struct Preamble {
+    packages: Vec<Package>,
+    commands: Vec<Command>,
+    theorems: Vec<TheoremKind>,
+}
+impl Preamble {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_package(&mut self, p: Package) {
+        self.packages.push(p);
+    }
+
+    pub fn remove_package(&mut self, name: &str) {
+        self.packages.retain(|x| x.name != name);
+    }
+
+    /// Replaces the command of the same name, if there is one, keeping its place.
+    pub fn add_command(&mut self, c: Command) {
+        match self.commands.iter_mut().find(|x| x.name == c.name) {
+            Some(existing) => *existing = c,
+            None => self.commands.push(c),
+        }
+    }
+
+    pub fn command(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|x| x.name == name)
+    }
+
+    /// Replaces the kind of the same name, if there is one, keeping its place.
+    /// A kind sharing another's counter has to come after it.
+    pub fn add_theorem(&mut self, t: TheoremKind) -> TexResult<()> {
+        self.add_theorem_with(t, &[])
+    }
+
+    /// `add_theorem`, where a counter can also be shared with one of the `predefined` kinds
+    /// (the ones the document class declares itself).
+    pub(crate) fn add_theorem_with(
+        &mut self,
+        t: TheoremKind,
+        predefined: &[&str],
+    ) -> TexResult<()> {
+        if let TheoremCounter::SharedWith(other) = &t.counter {
+            match self.theorem(other) {
+                Some(x) if x.counter != TheoremCounter::Unnumbered => {}
+                None if predefined.contains(&other.as_str()) => {}
+                _ => return Err(TexError::TheoremUndefined(other.clone())),
+            }
+        }
+        match self.theorems.iter_mut().find(|x| x.name == t.name) {
+            Some(existing) => *existing = t,
+            None => self.theorems.push(t),
+        }
+        Ok(())
+    }
+
+    pub fn theorem(&self, name: &str) -> Option<&TheoremKind> {
+        self.theorems.iter().find(|x| x.name == name)
+    }
+
+    pub fn theorems(&self) -> &[TheoremKind] {
+        &self.theorems
+    }
+
+    /// The packages as they will be loaded: these, then `extra`, one per name with options
+    /// merged, with hyperref and friends moved to the end.
+    pub fn packages<I: IntoIterator<Item = Package>>(&self, extra: I) -> TexResult<Vec<Package>> {
+        let (out, clashes) = self.packages_lenient(extra);
+        match clashes.into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(out),
+        }
+    }
+
+    /// `packages`, keeping the first value of any clashing option, and returning the clashes
+    /// alongside.
+    pub(crate) fn packages_lenient<I: IntoIterator<Item = Package>>(
+        &self,
+        extra: I,
+    ) -> (Vec<Package>, Vec<TexError>) {
+        let (mut out, clashes) = merge_packages_lenient(self.packages.iter().cloned().chain(extra));
+        out.sort_by_key(|x| load_order(&x.name));
+        (out, clashes)
+    }
+
+    /// The commands as they will be declared.
+    pub fn commands(&self) -> Vec<&Command> {
+        let mut out = vec![];
+        let mut seen = HashSet::new();
+        for c in &self.commands {
+            self.visit(c, &mut seen, &mut out);
+        }
+        out
+    }
+
+    /// Depth first, dependencies before dependents. Cycles are cut wherever they are found.
+    fn visit<'a>(
+        &'a self,
+        c: &'a Command,
+        seen: &mut HashSet<&'a str>,
+        out: &mut Vec<&'a Command>,
+    ) {
+        if !seen.insert(&c.name) {
+            return;
+        }
+        for dep in self
+            .commands
+            .iter()
+            .filter(|x| x.name != c.name && uses(&c.def, &x.name))
+        {
+            self.visit(dep, seen, out);
+        }
+        out.push(c);
+    }
+}
+
+/// Whether `def` calls `\name`. `\foo` doesn't count as a call of `\fo`.
+fn uses(def: &str, name: &str) -> bool {
+    let pat = format!("\\{}", name);
+    def.match_indices(&pat).any(|(i, _)| {
+        !def[i + pat.len()..]
+            .chars()
+            .next()
+            .is_some_and(|x| x.is_ascii_alphabetic())
+    })
+}
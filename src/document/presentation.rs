@@ -0,0 +1,228 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+/// The shape of the slides, beamer's `aspectratio` class option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AspectRatio {
+    /// 4:3, beamer's default.
+    FourThree,
+    SixteenNine,
+    SixteenTen,
+    FourteenNine,
+    FiveFour,
+    ThreeTwo,
+    /// 1.41:1, the shape of A4 paper.
+    Sqrt2,
+}
+impl AspectRatio {
+    pub fn value(&self) -> &'static str {
+        match &self {
+            AspectRatio::FourThree => "43",
+            AspectRatio::SixteenNine => "169",
+            AspectRatio::SixteenTen => "1610",
+            AspectRatio::FourteenNine => "149",
+            AspectRatio::FiveFour => "54",
+            AspectRatio::ThreeTwo => "32",
+            AspectRatio::Sqrt2 => "141",
+        }
+    }
+}
+
+/// What happens to the speaker notes of the frames (`Frame::add_note`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotesMode {
+    /// Left out.
+    #[default]
+    Hide,
+    /// A page of notes after each frame.
+    Show,
+    /// The notes next to the slides, on pages twice as wide; for a presenter's screen.
+    SecondScreen,
+    /// Only the notes, without the slides.
+    Only,
+}
+impl NotesMode {
+    /// The argument to `\setbeameroption`.
+    pub fn option(&self) -> &'static str {
+        match &self {
+            NotesMode::Hide => "hide notes",
+            NotesMode::Show => "show notes",
+            NotesMode::SecondScreen => "show notes on second screen",
+            NotesMode::Only => "show only notes",
+        }
+    }
+}
+
+/// A theme, and its options: `\usetheme[progressbar=frametitle]{metropolis}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub opt: Vec<String>,
+}
+impl Theme {
+    pub fn new(name: &str, opt: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            opt: opt.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn write(&self, kind: &str, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "\\use{}theme{}{{{}}}",
+            kind,
+            bracketed(&self.opt, ", "),
+            self.name
+        )
+    }
+}
+impl Opt for Theme {
+    fn add_option(&mut self, opt: &str) {
+        push_option(&mut self.opt, opt);
+    }
+}
+
+/// How a beamer document looks: its themes, templates, aspect ratio, whether it's a handout and
+/// what happens to the speaker notes. Only written for documents of class beamer; everything else ignores it.
+#[derive(Debug, Clone, Default)]
+pub struct Presentation {
+    theme: Option<Theme>,
+    color_theme: Option<Theme>,
+    font_theme: Option<Theme>,
+    inner_theme: Option<Theme>,
+    outer_theme: Option<Theme>,
+    /// The element, and whatever goes after it: `[frame number]` or `{...}`.
+    templates: Vec<(String, String)>,
+    aspect_ratio: Option<AspectRatio>,
+    handout: bool,
+    notes: NotesMode,
+}
+impl Presentation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_theme(&mut self, name: &str, opt: &[&str]) -> &mut Self {
+        self.theme = Some(Theme::new(name, opt));
+        self
+    }
+
+    pub fn set_color_theme(&mut self, name: &str, opt: &[&str]) -> &mut Self {
+        self.color_theme = Some(Theme::new(name, opt));
+        self
+    }
+
+    pub fn set_font_theme(&mut self, name: &str, opt: &[&str]) -> &mut Self {
+        self.font_theme = Some(Theme::new(name, opt));
+        self
+    }
+
+    pub fn set_inner_theme(&mut self, name: &str, opt: &[&str]) -> &mut Self {
+        self.inner_theme = Some(Theme::new(name, opt));
+        self
+    }
+
+    pub fn set_outer_theme(&mut self, name: &str, opt: &[&str]) -> &mut Self {
+        self.outer_theme = Some(Theme::new(name, opt));
+        self
+    }
+
+    pub fn theme(&self) -> Option<&Theme> {
+        self.theme.as_ref()
+    }
+
+    /// `\setbeamertemplate{element}{code}`. Replaces the element's template, if it already has
+    /// one.
+    pub fn set_template(&mut self, element: &str, code: &str) -> &mut Self {
+        self.put_template(element, format!("{{{}}}", code))
+    }
+
+    /// `\setbeamertemplate{element}[predefined]`, for the templates beamer comes with. Replaces
+    /// the element's template, if it already has one.
+    pub fn set_predefined_template(&mut self, element: &str, predefined: &str) -> &mut Self {
+        self.put_template(element, format!("[{}]", predefined))
+    }
+
+    /// No navigation symbols in the corner of each slide.
+    pub fn hide_navigation_symbols(&mut self) -> &mut Self {
+        self.set_template("navigation symbols", "")
+    }
+
+    /// A footline with just the frame number.
+    pub fn number_frames(&mut self) -> &mut Self {
+        self.set_predefined_template("footline", "frame number")
+    }
+
+    fn put_template(&mut self, element: &str, rest: String) -> &mut Self {
+        match self.templates.iter_mut().find(|x| x.0 == element) {
+            Some(existing) => existing.1 = rest,
+            None => self.templates.push((element.to_string(), rest)),
+        }
+        self
+    }
+
+    pub fn set_aspect_ratio(&mut self, ratio: AspectRatio) -> &mut Self {
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Handouts have one slide per frame, with all the overlays shown.
+    pub fn set_handout(&mut self, handout: bool) -> &mut Self {
+        self.handout = handout;
+        self
+    }
+
+    pub fn set_notes(&mut self, notes: NotesMode) -> &mut Self {
+        self.notes = notes;
+        self
+    }
+
+    pub fn notes(&self) -> NotesMode {
+        self.notes
+    }
+
+    /// Putting the notes on a second screen needs pgfpages.
+    pub fn packages(&self) -> Vec<Package> {
+        match self.notes {
+            NotesMode::SecondScreen => vec![package!("pgfpages")],
+            _ => vec![],
+        }
+    }
+
+    /// What it adds to the options of `\documentclass`.
+    pub(crate) fn class_options(&self) -> Vec<String> {
+        let mut out = vec![];
+        if let Some(r) = self.aspect_ratio {
+            out.push(format!("aspectratio={}", r.value()));
+        }
+        if self.handout {
+            out.push("handout".to_string());
+        }
+        out
+    }
+}
+impl AsLatex for Presentation {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        let themes = [
+            ("", &self.theme),
+            ("color", &self.color_theme),
+            ("font", &self.font_theme),
+            ("inner", &self.inner_theme),
+            ("outer", &self.outer_theme),
+        ];
+        for (kind, theme) in themes {
+            if let Some(t) = theme {
+                t.write(kind, w)?;
+            }
+        }
+        for (element, rest) in &self.templates {
+            writeln!(w, "\\setbeamertemplate{{{}}}{}", element, rest)?;
+        }
+        if self.notes != NotesMode::Hide {
+            writeln!(w, "\\setbeameroption{{{}}}", self.notes.option())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::prelude::*;
+
+/// The lines of the generated `.tex` a component ended up on. Lines are 1-based and inclusive.
+/// `path` runs from the outermost component down to this one, e.g. `["Section \"Results\"", "Table"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub path: Vec<String>,
+}
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (lines {}-{})",
+            self.path.join(" > "),
+            self.start,
+            self.end
+        )
+    }
+}
+
+/// Produced by `Document::write_mapped`. Maps lines of the output back to the components that
+/// wrote them, so that errors from the TeX engine can be pinned on something you recognise.
+/// Only structural components (anything with a rank below 10) get a span; text, commands and
+/// the like are attributed to whatever contains them.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    spans: Vec<Span>,
+}
+impl SourceMap {
+    /// In the order the components started.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// The innermost component covering `line`.
+    pub fn locate(&self, line: usize) -> Option<&Span> {
+        self.spans
+            .iter()
+            .filter(|x| x.start <= line && line <= x.end)
+            .max_by_key(|x| x.path.len())
+    }
+}
+
+struct Trace {
+    newlines: usize,
+    ended_on_newline: bool,
+    /// Open components: where they started, and whether they get a span at all.
+    open: Vec<(usize, Option<String>)>,
+    /// Number of open components whose insides are not traced.
+    opaque: usize,
+    spans: Vec<Span>,
+}
+
+thread_local! {
+    static TRACE: RefCell<Option<Trace>> = const { RefCell::new(None) };
+}
+
+/// Closes the component `enter` opened when dropped, so that it's closed however writing it
+/// ends.
+#[must_use]
+pub(crate) struct Entered<'a>(&'a Component);
+impl Drop for Entered<'_> {
+    fn drop(&mut self) {
+        exit(self.0);
+    }
+}
+
+/// Called by `Component::write_to` before writing anything, and kept until it's done. Does
+/// nothing unless a source map is being recorded on this thread.
+pub(crate) fn enter(c: &Component) -> Entered<'_> {
+    TRACE.with(|t| {
+        if let Some(t) = t.borrow_mut().as_mut() {
+            let label = (t.opaque == 0 && c.rank() < 10).then(|| c.describe());
+            if c.rank() >= 10 {
+                t.opaque += 1;
+            }
+            t.open.push((t.newlines + 1, label));
+        }
+    });
+    Entered(c)
+}
+
+fn exit(c: &Component) {
+    TRACE.with(|t| {
+        if let Some(t) = t.borrow_mut().as_mut() {
+            if c.rank() >= 10 {
+                t.opaque -= 1;
+            }
+            let end = if t.ended_on_newline {
+                t.newlines
+            } else {
+                t.newlines + 1
+            };
+            if let Some((start, Some(_))) = t.open.last() {
+                let start = *start;
+                let path = t.open.iter().filter_map(|x| x.1.clone()).collect();
+                t.spans.push(Span {
+                    start,
+                    end: end.max(start),
+                    path,
+                });
+            }
+            t.open.pop();
+        }
+    })
+}
+
+/// Counts the lines going through it, on behalf of the trace.
+struct Counter<'a> {
+    inner: &'a mut dyn Write,
+}
+impl Write for Counter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if n > 0 {
+            TRACE.with(|t| {
+                if let Some(t) = t.borrow_mut().as_mut() {
+                    t.newlines += buf[..n].iter().filter(|&&x| x == b'\n').count();
+                    t.ended_on_newline = buf[n - 1] == b'\n';
+                }
+            });
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Puts back whatever trace was installed before, when dropped.
+struct Installed(Option<Trace>);
+impl Drop for Installed {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        TRACE.with(|t| *t.borrow_mut() = previous);
+    }
+}
+
+/// Runs `f` with a fresh trace installed, writing through a line counter. The trace is taken
+/// down again however `f` ends.
+pub(crate) fn record<F>(w: &mut dyn Write, f: F) -> io::Result<SourceMap>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let installed = Installed(TRACE.with(|t| {
+        t.borrow_mut().replace(Trace {
+            newlines: 0,
+            ended_on_newline: false,
+            open: vec![],
+            opaque: 0,
+            spans: vec![],
+        })
+    }));
+    let res = f(&mut Counter { inner: w });
+    let trace = TRACE.with(|t| t.borrow_mut().take());
+    drop(installed);
+    res?;
+
+    let mut spans = trace.map(|t| t.spans).unwrap_or_default();
+    spans.sort_by_key(|x| (x.start, x.path.len()));
+    Ok(SourceMap { spans })
+}
@@ -1,5 +1,7 @@
 use std::{error::Error, fmt::Display, io};
 
+use crate::compile::CompileFailure;
+
 /// Your garden-variety custom error.
 /// Contains the catch-all `WhatEven` variant (WhatEven as in "What even is this?")
 /// If y'all want more, please put up an issue.
@@ -10,11 +12,42 @@ pub enum TexError {
     WhatEven(String),
     TraitUnimplemented(String),
     VariantUndefined,
-    LabelUndefined,
+    LabelUndefined(String),
+    LabelDuplicate(String),
+    LabelMismatch(String, String),
+    /// A label on an equation (or a line of one) that doesn't get a number.
+    LabelUnnumbered(String),
+    CitationUndefined(String),
+    CitationDuplicate(String),
+    BibError(String),
+    /// Row (the header being 0), columns in the table, columns in the row.
+    TableWidth(usize, usize, usize),
+    /// A long table inside a `TableFloat`, which longtable doesn't allow.
+    LongTableInFloat,
+    /// Columns in the table, and column specs given for it.
+    ColumnCount(usize, usize),
+    /// Package, and the two options that can't both be given.
+    PackageConflict(String, String, String),
+    /// A row that doesn't fit its math environment: the environment, and what's wrong.
+    EquationShape(String, String),
+    /// A theorem kind that hasn't been declared in the document.
+    TheoremUndefined(String),
+    /// Options that don't make sense, on their own or together.
+    InvalidOptions(String),
+    /// Algorithms written with both algorithm2e and algpseudocode, whose macros clash.
+    AlgorithmBackends,
+    /// How many overlays are outside of a beamer frame, where they do nothing.
+    OverlayOutsideFrame(usize),
     Undefined,
-    // #[cfg(feature = "markdown")]
-    // MarkdownError(String),
+    #[cfg(feature = "markdown")]
+    MarkdownError(String),
+    #[cfg(feature = "csv")]
+    CsvError(String),
     IoError(io::Error),
+    /// Everything `Document::validate` found.
+    Invalid(Vec<TexError>),
+    CompileFailed(Box<CompileFailure>),
+    MissingImage(String),
 }
 
 impl Display for TexError {
@@ -28,11 +61,37 @@ impl Display for TexError {
                 TexError::WhatEven(s) => s.to_string(),
                 TexError::Undefined => "Object not defined.".to_string(),
                 TexError::VariantUndefined => "The literal you provided does not correspond to a Variant. Please refer to the documentation for the list of valid literals.".to_string(),
-                TexError::LabelUndefined => "The label you provided does not exist.".to_string(),
-                TexError::TraitUnimplemented(s) => format!("{} does not implement the trait you desire (probably Populate).", s.to_string()),
-                // #[cfg(feature = "markdown")]
-                // TexError::MarkdownError(message) => message.to_string(),
-                TexError::IoError(e) => e.to_string()
+                TexError::LabelUndefined(s) => format!("The label {s} does not exist."),
+                TexError::LabelDuplicate(s) => format!("The label {s} is defined more than once."),
+                TexError::LabelMismatch(l, r) => format!("{r} refers to {l}, which is a different kind of label."),
+                TexError::LabelUnnumbered(s) => format!("{s} is on a line without an equation number, so there's nothing to refer to."),
+                TexError::CitationUndefined(s) => format!("{s} is cited, but is not in the bibliography."),
+                TexError::CitationDuplicate(s) => format!("The bibliography has more than one entry for {s}."),
+                TexError::BibError(message) => format!("Could not parse bibliography: {message}"),
+                TexError::PackageConflict(p, a, b) => format!("{p} is needed with both {a} and {b}."),
+                TexError::EquationShape(env, s) => format!("Can't add that row to {env}: {s}."),
+                TexError::TheoremUndefined(s) => format!("There is no theorem kind {s}; declare it with Document::new_theorem."),
+                TexError::InvalidOptions(s) => format!("Invalid options: {s}."),
+                TexError::AlgorithmBackends => "algorithm2e and algpseudocode can't be used in the same document.".to_string(),
+                TexError::OverlayOutsideFrame(n) => format!("{n} overlay(s) outside of a frame; they only work in beamer frames."),
+                TexError::TableWidth(r, t, n) => format!("Row {r} is {n} columns wide, but the table has {t}."),
+                TexError::LongTableInFloat => "A long table can't go in a table float; give it a caption instead.".to_string(),
+                TexError::ColumnCount(t, n) => format!("The table has {t} columns, but {n} column specs were given."),
+                TexError::TraitUnimplemented(s) => format!("{} does not implement the trait you desire (probably Populate).", s),
+                #[cfg(feature = "markdown")]
+                TexError::MarkdownError(message) => format!("Could not parse markdown: {message}"),
+                #[cfg(feature = "csv")]
+                TexError::CsvError(message) => format!("Could not read csv: {message}"),
+                TexError::IoError(e) => e.to_string(),
+                TexError::Invalid(v) => v.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("\n"),
+                TexError::CompileFailed(c) => format!(
+                    "{} failed on pass {} ({}). Build directory: {}",
+                    c.engine.program(),
+                    c.pass,
+                    c.status.map_or("killed".to_string(), |x| format!("exit status {x}")),
+                    c.build_dir.display()
+                ),
+                TexError::MissingImage(s) => format!("Image {s} is not in the graphics path.")
             }
         )?;
 
@@ -1,4 +1,9 @@
-use crate::prelude::AsLatex;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Label {
@@ -12,25 +17,37 @@ pub enum Label {
     Code(String),
     Item(String),
     Algorithm(String),
+    Theorem(String),
 }
 
 impl AsLatex for Label {
-    fn to_string(&self) -> String {
-        format!(
-            " \\label{{{}}} \n",
-            match &self {
-                Label::Standard(s) => format!("std:{s}"),
-                Label::Equation(s) => format!("eq:{s}"),
-                Label::Table(s) => format!("tab:{s}"),
-                Label::Figure(s) => format!("fig:{s}"),
-                Label::Section(s) => format!("sec:{s}"),
-                Label::Subsection(s) => format!("subsec:{s}"),
-                Label::Code(s) => format!("lst:{s}"),
-                Label::Item(s) => format!("itm:{s}"),
-                Label::Algorithm(s) => format!("alg:{s}"),
-                Label::Chapter(s) => format!("ch:{s}"),
-            }
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, " \\label{{{}}} ", self.key())
+    }
+}
+
+impl Label {
+    /// The prefix for this kind of label, and the name.
+    pub fn parts(&self) -> (&'static str, &str) {
+        match &self {
+            Label::Standard(s) => ("std", s),
+            Label::Equation(s) => ("eq", s),
+            Label::Table(s) => ("tab", s),
+            Label::Figure(s) => ("fig", s),
+            Label::Section(s) => ("sec", s),
+            Label::Subsection(s) => ("subsec", s),
+            Label::Code(s) => ("lst", s),
+            Label::Item(s) => ("itm", s),
+            Label::Algorithm(s) => ("alg", s),
+            Label::Chapter(s) => ("ch", s),
+            Label::Theorem(s) => ("thm", s),
+        }
+    }
+
+    /// What actually goes into \label{}, e.g. `fig:plot`.
+    pub fn key(&self) -> String {
+        let (prefix, name) = self.parts();
+        format!("{prefix}:{name}")
     }
 }
 
@@ -46,24 +63,63 @@ pub enum Reference {
     Code(String),
     Item(String),
     Algorithm(String),
+    Theorem(String),
 }
 
 impl AsLatex for Reference {
-    fn to_string(&self) -> String {
-        format!(
-            "~\\ref{{{}}} \n",
-            match &self {
-                Reference::Standard(s) => format!("std:{s}"),
-                Reference::Equation(s) => format!("eq:{s}"),
-                Reference::Table(s) => format!("tab:{s}"),
-                Reference::Figure(s) => format!("fig:{s}"),
-                Reference::Section(s) => format!("sec:{s}"),
-                Reference::Subsection(s) => format!("subsec:{s}"),
-                Reference::Code(s) => format!("lst:{s}"),
-                Reference::Item(s) => format!("itm:{s}"),
-                Reference::Algorithm(s) => format!("alg:{s}"),
-                Reference::Chapter(s) => format!("ch:{s}"),
-            }
-        )
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        writeln!(w, "~\\ref{{{}}} ", self.key())
+    }
+}
+
+impl Reference {
+    /// The prefix for this kind of reference, and the name.
+    pub fn parts(&self) -> (&'static str, &str) {
+        match &self {
+            Reference::Standard(s) => ("std", s),
+            Reference::Equation(s) => ("eq", s),
+            Reference::Table(s) => ("tab", s),
+            Reference::Figure(s) => ("fig", s),
+            Reference::Section(s) => ("sec", s),
+            Reference::Subsection(s) => ("subsec", s),
+            Reference::Code(s) => ("lst", s),
+            Reference::Item(s) => ("itm", s),
+            Reference::Algorithm(s) => ("alg", s),
+            Reference::Chapter(s) => ("ch", s),
+            Reference::Theorem(s) => ("thm", s),
+        }
     }
+
+    /// What actually goes into \ref{}, e.g. `fig:plot`.
+    pub fn key(&self) -> String {
+        let (prefix, name) = self.parts();
+        format!("{prefix}:{name}")
+    }
+}
+
+/// Cross-checks labels and references: duplicate labels, references to labels that don't exist,
+/// and references whose kind disagrees with the label of the same name (`Reference::Figure("x")`
+/// pointing at `Label::Table("x")`).
+pub fn check_labels(labels: &[&Label], references: &[&Reference]) -> Vec<TexError> {
+    let mut out = vec![];
+
+    let mut seen: HashMap<String, &Label> = HashMap::new();
+    for l in labels {
+        if seen.insert(l.key(), l).is_some() {
+            out.push(TexError::LabelDuplicate(l.key()));
+        }
+    }
+
+    for r in references {
+        if seen.contains_key(&r.key()) {
+            continue;
+        }
+        let name = r.parts().1;
+        match labels.iter().find(|l| l.parts().1 == name) {
+            Some(l) => out.push(TexError::LabelMismatch(l.key(), r.key())),
+            None => out.push(TexError::LabelUndefined(r.key())),
+        }
+    }
+
+    out
 }
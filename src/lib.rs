@@ -10,9 +10,15 @@
 //!
 //! A shout-out to another crate, `tex-rs`. A few of the design choices I made are based on this crate.
 
+/// Citations, and the entries they point to.
+pub mod bibliography;
+
 /// Bunch of From<>s, they feel like they might be useful
 pub mod casting;
 
+/// From a `Document` to a pdf.
+pub mod compile;
+
 /// Latex commands/macros. Haven't found this in any other crate.
 pub mod commands;
 
@@ -28,28 +34,40 @@ pub mod errors;
 /// Really helpful stuff.
 pub mod macros;
 
+/// Formulas as trees, rather than strings.
+pub mod math;
+
 /// Ubiquitous.
 pub mod traits;
 
 pub mod label;
 
-// #[cfg(feature = "markdown")]
-// pub mod markdown;
+/// Markdown to `Component`s.
+#[cfg(feature = "markdown")]
+pub mod markdown;
 
-///
 #[cfg(test)]
 mod tests;
 
 pub mod prelude {
+    pub use crate::bibliography::*;
     pub use crate::commands::*;
+    pub use crate::compile::*;
     pub use crate::component::*;
     pub use crate::document::*;
     pub use crate::errors::*;
     pub use crate::label::*;
+    pub use crate::math::*;
     pub use crate::traits::*;
 
     pub fn escape(s: &str, esc: Option<&[char]>) -> String {
-        if esc.is_none() {
+        if let Some(esc) = esc {
+            let mut s = s.to_string();
+            for c in esc {
+                s = s.replace(&c.to_string(), &format!("\\{}", c));
+            }
+            s
+        } else {
             s.replace("_", "\\_")
                 .replace("^", "\\^")
                 .replace("#", "\\#")
@@ -58,19 +76,31 @@ pub mod prelude {
                 .replace("$", "\\$")
                 .replace("{", "\\{")
                 .replace("}", "\\}")
-        } else {
-            let esc = esc.unwrap();
-            let mut s = s.to_string();
-            for c in esc {
-                s = s.replace(&c.to_string(), &format!("\\{}", c));
+        }
+    }
+
+    /// Escapes every character that means something to latex, for text outside of math.
+    /// Backslashes, tildes and carets become `\textbackslash{}` and friends.
+    pub fn escape_text(s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '\\' => out.push_str("\\textbackslash{}"),
+                '~' => out.push_str("\\textasciitilde{}"),
+                '^' => out.push_str("\\textasciicircum{}"),
+                '_' | '#' | '&' | '%' | '$' | '{' | '}' => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                c => out.push(c),
             }
-            s
         }
+        out
     }
 
     // All the macros, again.
     pub use crate::{
-        builtin, chapter, command, document, environment, figure, frame, image, label, package,
-        part, reference, row, section, tabular, textchunk, unwrap,
+        builtin, chapter, cite, command, document, environment, figure, frame, image, label,
+        package, part, reference, row, section, tabular, textchunk, theorem, unwrap,
     };
 }
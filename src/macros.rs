@@ -70,7 +70,7 @@ macro_rules! environment {
 /// This could've gotten real ugly if you had to do it yourself.
 /// So whenever you've got a latex macro you defined earlier, and want to use it, use this macro.
 /// ```rust
-/// use rust_texas::*;
+/// use rust_texas::prelude::*;
 /// fn dummy() -> Result<(), Box<dyn std::error::Error>> {
 ///     let mut doc = document!("article");
 ///     command!(doc, "<your command/macro name>", "<appropriate arguments to the command>");
@@ -89,6 +89,17 @@ macro_rules! command {
     };
 }
 
+/// Sugar for theorem creation. Like `command!`, fails if the kind isn't declared in the document.
+#[macro_export]
+macro_rules! theorem {
+    ($doc:ident, $kind:literal) => {
+        Theorem::new(&$doc.get_theorem($kind)?)
+    };
+    ($doc:ident, $kind:ident) => {
+        Theorem::new(&$doc.get_theorem($kind)?)
+    };
+}
+
 /// Sugar for document creation
 #[macro_export]
 macro_rules! document {
@@ -203,3 +214,15 @@ macro_rules! reference {
         Component::Reference(<&str as Into<Reference>>::into($reference))
     };
 }
+
+/// Sugar for \cite{} creation: `cite!("a", "b")`, or `cite!(parencite; "a")` for the other kinds.
+/// An unknown kind is a `TexError::VariantUndefined`, hence the `?`.
+#[macro_export]
+macro_rules! cite {
+    ($($key:literal),+) => {
+        Component::Citation(Citation::new(CiteKind::Cite, &[$($key),+]))
+    };
+    ($kind:ident; $($key:literal),+) => {
+        Component::Citation(Citation::new(CiteKind::try_from(stringify!($kind))?, &[$($key),+]))
+    };
+}
@@ -0,0 +1,317 @@
+use std::{collections::HashMap, fs::read_to_string};
+
+use ::markdown::{
+    mdast::{self, Node},
+    to_mdast, Constructs, ParseOptions,
+};
+
+use crate::prelude::*;
+
+/// Walks the mdast of a markdown file and turns it into `Component`s.
+///
+/// Headings are mapped onto the document hierarchy using `Component::rank`: a `#` heading becomes
+/// the component of rank `top_rank` (a `Section` by default), `##` the next one down, and so on.
+/// Headings that fall below `Subsection` become a bold line inside a `Paragraph`.
+///
+/// ```rust
+/// use rust_texas::prelude::*;
+/// use rust_texas::markdown::MarkdownImporter;
+/// fn dummy() -> TexResult<()> {
+///     let mut doc = document!("article");
+///     doc.attach_vec(MarkdownImporter::new().import("# Notes\n\nHello, *world*.")?)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MarkdownImporter {
+    top_rank: u8,
+}
+impl Default for MarkdownImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl MarkdownImporter {
+    /// `#` becomes a `Section`.
+    pub fn new() -> Self {
+        Self { top_rank: 2 }
+    }
+
+    /// `#` becomes whatever has rank `rank`: 0 for `Part`, 1 for `Chapter`, 2 for `Section`, 3 for `Subsection`.
+    pub fn with_top_rank(rank: u8) -> TexResult<Self> {
+        if rank > 3 {
+            return Err(TexError::InvalidOptions(format!(
+                "the top rank has to be between 0 and 3, not {}",
+                rank
+            )));
+        }
+        Ok(Self { top_rank: rank })
+    }
+
+    pub fn import(&self, src: &str) -> TexResult<Vec<Component>> {
+        let options = ParseOptions {
+            constructs: Constructs {
+                math_flow: true,
+                math_text: true,
+                ..Constructs::gfm()
+            },
+            ..ParseOptions::gfm()
+        };
+        let root = to_mdast(src, &options).map_err(|e| TexError::MarkdownError(e.to_string()))?;
+        let blocks = root.children().cloned().unwrap_or_default();
+
+        let mut walker = Walker {
+            top_rank: self.top_rank,
+            footnotes: HashMap::new(),
+        };
+        walker.collect_footnotes(&blocks);
+        walker.hierarchy(&blocks)
+    }
+
+    pub fn import_file(&self, path: &str) -> TexResult<Vec<Component>> {
+        self.import(&read_to_string(path)?)
+    }
+}
+
+struct Walker {
+    top_rank: u8,
+    footnotes: HashMap<String, String>,
+}
+impl Walker {
+    fn collect_footnotes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            if let Node::FootnoteDefinition(def) = node {
+                let body = def
+                    .children
+                    .iter()
+                    .map(|x| self.inline_children(x))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.footnotes.insert(def.identifier.clone(), body);
+            }
+        }
+    }
+
+    /// Builds the section tree. Open headings live on a stack, and get attached to their parent
+    /// once a heading of equal or higher rank shows up.
+    fn hierarchy(&self, nodes: &[Node]) -> TexResult<Vec<Component>> {
+        let mut out: Vec<Component> = vec![];
+        let mut stack: Vec<Component> = vec![];
+
+        for node in nodes {
+            if let Node::Heading(h) = node {
+                let rank = self.top_rank + h.depth - 1;
+                if let Some(heading) = heading(rank, &self.inline_children(node)) {
+                    while stack.last().is_some_and(|x| x.rank() >= rank) {
+                        let done = stack.pop().unwrap();
+                        Self::place(&mut out, &mut stack, done)?;
+                    }
+                    stack.push(heading);
+                    continue;
+                }
+            }
+            for c in self.block(node)? {
+                Self::place(&mut out, &mut stack, c)?;
+            }
+        }
+        while let Some(done) = stack.pop() {
+            Self::place(&mut out, &mut stack, done)?;
+        }
+
+        Ok(out)
+    }
+
+    fn place(out: &mut Vec<Component>, stack: &mut [Component], c: Component) -> TexResult<()> {
+        match stack.last_mut() {
+            Some(parent) => {
+                parent.attach(c)?;
+            }
+            None => out.push(c),
+        }
+        Ok(())
+    }
+
+    fn block(&self, node: &Node) -> TexResult<Vec<Component>> {
+        let out = match node {
+            Node::Paragraph(p) => {
+                vec![Component::Paragraph(Paragraph::with_components(
+                    self.phrasing(&p.children),
+                ))]
+            }
+            Node::Heading(_) => {
+                // Too deep for the hierarchy.
+                let title = TextChunk::new(&self.inline_children(node), TextType::Bold);
+                vec![Component::Paragraph(Paragraph::with_components(vec![
+                    Component::TextChunk(title),
+                ]))]
+            }
+            Node::List(l) => vec![Component::List(self.list(l)?)],
+            Node::Code(c) => {
+                let mut env = Environment::new("verbatim");
+                env.attach(Component::TextChunk(TextChunk::raw(&c.value)))?;
+                vec![Component::Environment(env)]
+            }
+            Node::Math(m) => vec![Component::TextChunk(TextChunk::new(
+                &m.value,
+                TextType::DisplayMath,
+            ))],
+            Node::Blockquote(q) => {
+                let mut env = Environment::new("quote");
+                for c in &q.children {
+                    env.attach_vec(self.block(c)?)?;
+                }
+                vec![Component::Environment(env)]
+            }
+            Node::Table(t) => vec![Component::Table(self.table(t)?)],
+            Node::ThematicBreak(_) => vec![Component::Command(
+                r"\par\noindent\rule{\linewidth}{0.4pt}\par".to_string(),
+            )],
+            // Frontmatter, html, definitions and footnote definitions (handled separately) are dropped.
+            _ => vec![],
+        };
+        Ok(out)
+    }
+
+    fn list(&self, l: &mdast::List) -> TexResult<List> {
+        let mut list = List::new(if l.ordered {
+            ListType::Enumerate
+        } else {
+            ListType::Itemize
+        });
+        for item in &l.children {
+            let children = item.children().cloned().unwrap_or_default();
+            let item = match children.as_slice() {
+                [Node::Paragraph(p)] => {
+                    Component::TextChunk(TextChunk::raw(self.phrasing_latex(&p.children).trim()))
+                }
+                _ => {
+                    let mut comps = vec![];
+                    for c in &children {
+                        comps.extend(self.block(c)?);
+                    }
+                    Component::Paragraph(Paragraph::with_components(comps))
+                }
+            };
+            list.attach(item)?;
+        }
+        Ok(list)
+    }
+
+    fn table(&self, t: &mdast::Table) -> TexResult<Table> {
+        let mut rows = t.children.iter().map(|row| {
+            Row::with_cells(
+                row.children()
+                    .map(|cells| {
+                        cells
+                            .iter()
+                            .map(|cell| {
+                                Component::TextChunk(TextChunk::raw(&self.inline_children(cell)))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            )
+        });
+        let head = rows.next().unwrap_or_default();
+        let mut table = Table::with_columns(
+            t.align
+                .iter()
+                .map(|x| match x {
+                    // Markdown tables are left-aligned unless they say otherwise.
+                    mdast::AlignKind::Left | mdast::AlignKind::None => ColumnSpec::Left,
+                    mdast::AlignKind::Right => ColumnSpec::Right,
+                    mdast::AlignKind::Center => ColumnSpec::Center,
+                })
+                .collect(),
+            head,
+        );
+        table.attach_iter(rows.map(Component::Row))?;
+        Ok(table)
+    }
+
+    /// Inline content, as components. Text is merged into as few `TextChunk`s as possible.
+    /// Images and strikethrough get their own component, so the document knows to load
+    /// graphicx and ulem for them.
+    fn phrasing(&self, nodes: &[Node]) -> Vec<Component> {
+        let mut out = vec![];
+        let mut buf = String::new();
+        for node in nodes {
+            let own = match node {
+                Node::Image(img) => Component::Image(Image::new(&img.url)),
+                Node::Delete(_) => Component::TextChunk(TextChunk::new(
+                    &self.inline_children(node),
+                    TextType::Strikethrough,
+                )),
+                _ => {
+                    buf.push_str(&self.inline(node));
+                    continue;
+                }
+            };
+            if !buf.trim().is_empty() {
+                out.push(Component::TextChunk(TextChunk::raw(buf.trim())));
+            }
+            buf.clear();
+            out.push(own);
+        }
+        if !buf.trim().is_empty() {
+            out.push(Component::TextChunk(TextChunk::raw(buf.trim())));
+        }
+        out
+    }
+
+    fn phrasing_latex(&self, nodes: &[Node]) -> String {
+        nodes.iter().map(|x| self.inline(x)).collect()
+    }
+
+    fn inline_children(&self, node: &Node) -> String {
+        node.children()
+            .map(|x| self.phrasing_latex(x))
+            .unwrap_or_default()
+    }
+
+    fn inline(&self, node: &Node) -> String {
+        match node {
+            Node::Text(t) => escape(&t.value, None),
+            Node::Emphasis(_) => {
+                TextChunk::new(&self.inline_children(node), TextType::Italic).to_string()
+            }
+            Node::Strong(_) => {
+                TextChunk::new(&self.inline_children(node), TextType::Bold).to_string()
+            }
+            Node::Delete(_) => {
+                TextChunk::new(&self.inline_children(node), TextType::Strikethrough).to_string()
+            }
+            Node::InlineCode(c) => {
+                TextChunk::new(&escape(&c.value, None), TextType::Teletype).to_string()
+            }
+            Node::InlineMath(m) => TextChunk::new(&m.value, TextType::InlineMath).to_string(),
+            Node::Link(l) => format!(
+                "\\href{{{}}}{{{}}}",
+                escape(&l.url, Some(&['#', '%'])),
+                self.inline_children(node)
+            ),
+            Node::Break(_) => "\\\\\n".to_string(),
+            Node::Image(img) => Image::new(&img.url).to_string(),
+            Node::FootnoteReference(r) => match self.footnotes.get(&r.identifier) {
+                Some(body) => format!("\\footnote{{{}}}", body),
+                None => String::new(),
+            },
+            Node::Paragraph(_) | Node::LinkReference(_) => self.inline_children(node),
+            _ => String::new(),
+        }
+    }
+}
+
+/// The hierarchy component of a given rank, if there is one. `name` is already latex, so it
+/// skips the escaping the constructors do.
+fn heading(rank: u8, name: &str) -> Option<Component> {
+    let name = name.trim().to_string();
+    let components = vec![];
+    match rank {
+        0 => Some(Component::Part(Part { name, components })),
+        1 => Some(Component::Chapter(Chapter { name, components })),
+        2 => Some(Component::Section(Section { name, components })),
+        3 => Some(Component::Subsection(Subsection { name, components })),
+        _ => None,
+    }
+}
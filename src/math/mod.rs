@@ -0,0 +1,474 @@
+use std::io::{self, Write};
+
+use crate::prelude::*;
+
+mod ops;
+
+/// Binary operators and relations, loosest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Approx,
+    Add,
+    Sub,
+    Mul,
+    Times,
+}
+impl BinOp {
+    pub fn symbol(&self) -> &str {
+        match &self {
+            BinOp::Eq => "=",
+            BinOp::Neq => "\\neq",
+            BinOp::Lt => "<",
+            BinOp::Le => "\\leq",
+            BinOp::Gt => ">",
+            BinOp::Ge => "\\geq",
+            BinOp::Approx => "\\approx",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "\\cdot",
+            BinOp::Times => "\\times",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match &self {
+            BinOp::Eq
+            | BinOp::Neq
+            | BinOp::Lt
+            | BinOp::Le
+            | BinOp::Gt
+            | BinOp::Ge
+            | BinOp::Approx => 0,
+            BinOp::Add | BinOp::Sub => 1,
+            BinOp::Mul | BinOp::Times => 2,
+        }
+    }
+
+    /// `a - (b - c)` needs its brackets, `a + (b + c)` doesn't. Relations chain.
+    fn associative(&self) -> bool {
+        !matches!(self, BinOp::Sub)
+    }
+}
+
+/// `\sum`, `\prod` and friends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BigOp {
+    Sum,
+    Prod,
+    Union,
+    Intersection,
+}
+impl BigOp {
+    pub fn command(&self) -> &str {
+        match &self {
+            BigOp::Sum => "\\sum",
+            BigOp::Prod => "\\prod",
+            BigOp::Union => "\\bigcup",
+            BigOp::Intersection => "\\bigcap",
+        }
+    }
+}
+
+/// The amsmath matrix environments, by delimiter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixKind {
+    Plain,
+    Paren,
+    Bracket,
+    Brace,
+    Vert,
+    DoubleVert,
+}
+impl MatrixKind {
+    pub fn env(&self) -> &str {
+        match &self {
+            MatrixKind::Plain => "matrix",
+            MatrixKind::Paren => "pmatrix",
+            MatrixKind::Bracket => "bmatrix",
+            MatrixKind::Brace => "Bmatrix",
+            MatrixKind::Vert => "vmatrix",
+            MatrixKind::DoubleVert => "Vmatrix",
+        }
+    }
+}
+
+/// Functions that have their own command, as opposed to needing `\operatorname`.
+const NAMED_FUNCTIONS: [&str; 32] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "log", "ln", "lg", "exp", "arg", "min", "max", "inf", "sup", "lim", "liminf", "limsup",
+    "det", "dim", "gcd", "ker", "deg", "hom", "Pr",
+];
+
+/// A formula, as a tree. Renders to latex with only the brackets it needs.
+///
+/// Build it with the constructors and the usual operators:
+/// ```rust
+/// use rust_texas::prelude::*;
+/// let x = Expr::var("x");
+/// let e = (x.clone() + 1).pow(2) / Expr::sqrt(x);
+/// assert_eq!(e.to_string(), "\\frac{\\left(x + 1\\right)^{2}}{\\sqrt{x}}");
+/// ```
+/// `*` is `\cdot`, `/` is a fraction, and numbers (`i64`, `f64`) can be used wherever an `Expr`
+/// can. Variables are written as given, so `Expr::var("\\alpha")` works.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Var(String),
+    /// Always non-negative; negative numbers become `Neg`.
+    Num(String),
+    /// `\text{}`, for words inside formulas.
+    Text(String),
+    /// Written out as-is, and treated as atomic.
+    Raw(String),
+    Neg(Box<Expr>),
+    Bin(BinOp, Box<Expr>, Box<Expr>),
+    Frac(Box<Expr>, Box<Expr>),
+    /// The radicand, and the index for anything other than square roots.
+    Root(Box<Expr>, Option<Box<Expr>>),
+    /// Base, subscript, superscript.
+    Script(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    /// Lower bound, upper bound, integrand, variable of integration.
+    Integral(Option<Box<Expr>>, Option<Box<Expr>>, Box<Expr>, Box<Expr>),
+    /// Operator, lower bound, upper bound, body.
+    Big(BigOp, Option<Box<Expr>>, Option<Box<Expr>>, Box<Expr>),
+    /// Variable, what it tends to, body.
+    Limit(Box<Expr>, Box<Expr>, Box<Expr>),
+    Matrix(MatrixKind, Vec<Vec<Expr>>),
+    /// Function name (without the backslash) and arguments.
+    Apply(String, Vec<Expr>),
+    /// Explicit brackets, `\left( ... \right)`.
+    Group(Box<Expr>),
+}
+
+impl Expr {
+    pub fn var(name: &str) -> Self {
+        Self::Var(name.to_string())
+    }
+
+    pub fn num<N: Into<Expr>>(n: N) -> Self {
+        n.into()
+    }
+
+    pub fn text(s: &str) -> Self {
+        Self::Text(s.to_string())
+    }
+
+    pub fn raw(s: &str) -> Self {
+        Self::Raw(s.to_string())
+    }
+
+    pub fn frac<A: Into<Expr>, B: Into<Expr>>(num: A, den: B) -> Self {
+        Self::Frac(Box::new(num.into()), Box::new(den.into()))
+    }
+
+    pub fn sqrt<A: Into<Expr>>(x: A) -> Self {
+        Self::Root(Box::new(x.into()), None)
+    }
+
+    pub fn root<A: Into<Expr>, B: Into<Expr>>(n: A, x: B) -> Self {
+        Self::Root(Box::new(x.into()), Some(Box::new(n.into())))
+    }
+
+    pub fn integral<A: Into<Expr>>(
+        lower: Option<Expr>,
+        upper: Option<Expr>,
+        body: A,
+        var: &str,
+    ) -> Self {
+        Self::Integral(
+            lower.map(Box::new),
+            upper.map(Box::new),
+            Box::new(body.into()),
+            Box::new(Self::var(var)),
+        )
+    }
+
+    pub fn big<A: Into<Expr>>(
+        op: BigOp,
+        lower: Option<Expr>,
+        upper: Option<Expr>,
+        body: A,
+    ) -> Self {
+        Self::Big(
+            op,
+            lower.map(Box::new),
+            upper.map(Box::new),
+            Box::new(body.into()),
+        )
+    }
+
+    pub fn sum<A: Into<Expr>>(lower: Option<Expr>, upper: Option<Expr>, body: A) -> Self {
+        Self::big(BigOp::Sum, lower, upper, body)
+    }
+
+    pub fn prod<A: Into<Expr>>(lower: Option<Expr>, upper: Option<Expr>, body: A) -> Self {
+        Self::big(BigOp::Prod, lower, upper, body)
+    }
+
+    pub fn limit<A: Into<Expr>, B: Into<Expr>>(var: &str, to: A, body: B) -> Self {
+        Self::Limit(
+            Box::new(Self::var(var)),
+            Box::new(to.into()),
+            Box::new(body.into()),
+        )
+    }
+
+    pub fn matrix(kind: MatrixKind, rows: Vec<Vec<Expr>>) -> Self {
+        Self::Matrix(kind, rows)
+    }
+
+    pub fn apply(func: &str, args: Vec<Expr>) -> Self {
+        Self::Apply(func.to_string(), args)
+    }
+
+    pub fn group<A: Into<Expr>>(x: A) -> Self {
+        Self::Group(Box::new(x.into()))
+    }
+
+    /// `self^{exp}`. Keeps the subscript, if there is one.
+    pub fn pow<A: Into<Expr>>(self, exp: A) -> Self {
+        match self {
+            Self::Script(base, sub, None) => Self::Script(base, sub, Some(Box::new(exp.into()))),
+            base => Self::Script(Box::new(base), None, Some(Box::new(exp.into()))),
+        }
+    }
+
+    /// `self_{sub}`. Keeps the superscript, if there is one.
+    pub fn subscript<A: Into<Expr>>(self, sub: A) -> Self {
+        match self {
+            Self::Script(base, None, sup) => Self::Script(base, Some(Box::new(sub.into())), sup),
+            base => Self::Script(Box::new(base), Some(Box::new(sub.into())), None),
+        }
+    }
+
+    pub fn rel<A: Into<Expr>>(self, op: BinOp, rhs: A) -> Self {
+        Self::Bin(op, Box::new(self), Box::new(rhs.into()))
+    }
+
+    /// `self = rhs`. Not `==`, because that's `PartialEq`.
+    pub fn equals<A: Into<Expr>>(self, rhs: A) -> Self {
+        self.rel(BinOp::Eq, rhs)
+    }
+
+    pub fn times<A: Into<Expr>>(self, rhs: A) -> Self {
+        self.rel(BinOp::Times, rhs)
+    }
+
+    /// How tightly this binds. Anything that can be a script base without brackets is 5.
+    /// Big operators, integrals and limits swallow everything to their right, so they also need
+    /// brackets on the left of a product (see `open_right`).
+    fn precedence(&self) -> u8 {
+        match &self {
+            Expr::Bin(op, _, _) => op.precedence(),
+            Expr::Neg(_) => 1,
+            Expr::Big(..) | Expr::Integral(..) | Expr::Limit(..) => 2,
+            Expr::Apply(..) => 3,
+            Expr::Script(..) | Expr::Frac(..) => 4,
+            Expr::Var(_)
+            | Expr::Num(_)
+            | Expr::Text(_)
+            | Expr::Raw(_)
+            | Expr::Root(..)
+            | Expr::Matrix(..)
+            | Expr::Group(_) => 5,
+        }
+    }
+
+    fn open_right(&self) -> bool {
+        matches!(self, Expr::Big(..) | Expr::Integral(..) | Expr::Limit(..))
+    }
+
+    /// Packages needed by anything in the tree: amsmath for matrices, `\text` and `\operatorname`.
+    pub fn packages(&self) -> Vec<Package> {
+        let mut amsmath = false;
+        self.walk(&mut |x| {
+            amsmath |= match x {
+                Expr::Matrix(..) | Expr::Text(_) => true,
+                Expr::Apply(func, _) => {
+                    !NAMED_FUNCTIONS.contains(&func.as_str()) && func.chars().count() > 1
+                }
+                _ => false,
+            }
+        });
+        if amsmath {
+            vec![package!("amsmath")]
+        } else {
+            vec![]
+        }
+    }
+
+    /// Every subexpression, this one included, depth first.
+    pub fn walk<'a, F: FnMut(&'a Expr)>(&'a self, f: &mut F) {
+        f(self);
+        let children: Vec<&Expr> = match &self {
+            Expr::Var(_) | Expr::Num(_) | Expr::Text(_) | Expr::Raw(_) => vec![],
+            Expr::Neg(a) | Expr::Group(a) => vec![a],
+            Expr::Bin(_, a, b) | Expr::Frac(a, b) => vec![a, b],
+            Expr::Root(a, b) => [Some(a), b.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|x| &**x)
+                .collect(),
+            Expr::Script(a, b, c) => [Some(a), b.as_ref(), c.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|x| &**x)
+                .collect(),
+            Expr::Integral(a, b, c, d) => [a.as_ref(), b.as_ref(), Some(c), Some(d)]
+                .into_iter()
+                .flatten()
+                .map(|x| &**x)
+                .collect(),
+            Expr::Big(_, a, b, c) => [a.as_ref(), b.as_ref(), Some(c)]
+                .into_iter()
+                .flatten()
+                .map(|x| &**x)
+                .collect(),
+            Expr::Limit(a, b, c) => vec![a, b, c],
+            Expr::Matrix(_, rows) => rows.iter().flatten().collect(),
+            Expr::Apply(_, args) => args.iter().collect(),
+        };
+        for c in children {
+            c.walk(f);
+        }
+    }
+}
+
+/// `\left( ... \right)` around `e` if `paren`.
+fn write_maybe_paren(e: &Expr, paren: bool, w: &mut dyn Write) -> io::Result<()> {
+    if paren {
+        write!(w, "\\left(")?;
+        e.write_to(w)?;
+        write!(w, "\\right)")
+    } else {
+        e.write_to(w)
+    }
+}
+
+/// `{e}`
+fn write_braced(e: &Expr, w: &mut dyn Write) -> io::Result<()> {
+    write!(w, "{{")?;
+    e.write_to(w)?;
+    write!(w, "}}")
+}
+
+fn write_limits(
+    lower: &Option<Box<Expr>>,
+    upper: &Option<Box<Expr>>,
+    w: &mut dyn Write,
+) -> io::Result<()> {
+    if let Some(l) = lower {
+        write!(w, "_")?;
+        write_braced(l, w)?;
+    }
+    if let Some(u) = upper {
+        write!(w, "^")?;
+        write_braced(u, w)?;
+    }
+    Ok(())
+}
+
+impl AsLatex for Expr {
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()> {
+        match &self {
+            Expr::Var(s) | Expr::Num(s) | Expr::Raw(s) => write!(w, "{}", s),
+            Expr::Text(s) => write!(w, "\\text{{{}}}", s),
+            Expr::Neg(a) => {
+                write!(w, "-")?;
+                write_maybe_paren(a, a.precedence() <= 1, w)
+            }
+            Expr::Bin(op, a, b) => {
+                let p = op.precedence();
+                write_maybe_paren(a, a.precedence() < p || (a.open_right() && p >= 2), w)?;
+                // An empty left hand side is how alignment points before a relation are spelled.
+                if **a == Expr::Raw(String::new()) {
+                    write!(w, "{} ", op.symbol())?;
+                } else {
+                    write!(w, " {} ", op.symbol())?;
+                }
+                let paren = b.precedence() < p
+                    || (b.precedence() == p && !op.associative())
+                    || matches!(**b, Expr::Neg(_));
+                write_maybe_paren(b, paren, w)
+            }
+            Expr::Frac(a, b) => {
+                write!(w, "\\frac")?;
+                write_braced(a, w)?;
+                write_braced(b, w)
+            }
+            Expr::Root(a, n) => {
+                write!(w, "\\sqrt")?;
+                if let Some(n) = n {
+                    write!(w, "[")?;
+                    n.write_to(w)?;
+                    write!(w, "]")?;
+                }
+                write_braced(a, w)
+            }
+            Expr::Script(base, sub, sup) => {
+                write_maybe_paren(base, base.precedence() < 5, w)?;
+                write_limits(sub, sup, w)
+            }
+            Expr::Integral(lower, upper, body, var) => {
+                write!(w, "\\int")?;
+                write_limits(lower, upper, w)?;
+                write!(w, " ")?;
+                write_maybe_paren(body, body.precedence() < 2, w)?;
+                write!(w, " \\,d")?;
+                var.write_to(w)
+            }
+            Expr::Big(op, lower, upper, body) => {
+                write!(w, "{}", op.command())?;
+                write_limits(lower, upper, w)?;
+                write!(w, " ")?;
+                write_maybe_paren(body, body.precedence() < 2, w)
+            }
+            Expr::Limit(var, to, body) => {
+                write!(w, "\\lim_{{")?;
+                var.write_to(w)?;
+                write!(w, " \\to ")?;
+                to.write_to(w)?;
+                write!(w, "}} ")?;
+                write_maybe_paren(body, body.precedence() < 2, w)
+            }
+            Expr::Matrix(kind, rows) => {
+                write!(w, "\\begin{{{}}} ", kind.env())?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, " \\\\ ")?;
+                    }
+                    for (j, cell) in row.iter().enumerate() {
+                        if j > 0 {
+                            write!(w, " & ")?;
+                        }
+                        cell.write_to(w)?;
+                    }
+                }
+                write!(w, " \\end{{{}}}", kind.env())
+            }
+            Expr::Apply(func, args) => {
+                if NAMED_FUNCTIONS.contains(&func.as_str()) {
+                    write!(w, "\\{}", func)?;
+                } else if func.chars().count() == 1 {
+                    write!(w, "{}", func)?;
+                } else {
+                    write!(w, "\\operatorname{{{}}}", func)?;
+                }
+                write!(w, "\\left(")?;
+                for (i, a) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, ", ")?;
+                    }
+                    a.write_to(w)?;
+                }
+                write!(w, "\\right)")
+            }
+            Expr::Group(a) => write_maybe_paren(a, true, w),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops;
+
+use crate::prelude::*;
+
+impl From<i64> for Expr {
+    fn from(value: i64) -> Self {
+        if value < 0 {
+            Expr::Neg(Box::new(Expr::Num(value.unsigned_abs().to_string())))
+        } else {
+            Expr::Num(value.to_string())
+        }
+    }
+}
+
+impl From<i32> for Expr {
+    fn from(value: i32) -> Self {
+        Expr::from(value as i64)
+    }
+}
+
+impl From<f64> for Expr {
+    fn from(value: f64) -> Self {
+        if value.is_sign_negative() && value != 0.0 {
+            Expr::Neg(Box::new(Expr::Num((-value).to_string())))
+        } else {
+            Expr::Num(value.to_string())
+        }
+    }
+}
+
+impl From<&str> for Expr {
+    fn from(value: &str) -> Self {
+        Expr::var(value)
+    }
+}
+
+impl<T: Into<Expr>> ops::Add<T> for Expr {
+    type Output = Expr;
+    fn add(self, rhs: T) -> Self::Output {
+        self.rel(BinOp::Add, rhs)
+    }
+}
+
+impl<T: Into<Expr>> ops::Sub<T> for Expr {
+    type Output = Expr;
+    fn sub(self, rhs: T) -> Self::Output {
+        self.rel(BinOp::Sub, rhs)
+    }
+}
+
+impl<T: Into<Expr>> ops::Mul<T> for Expr {
+    type Output = Expr;
+    fn mul(self, rhs: T) -> Self::Output {
+        self.rel(BinOp::Mul, rhs)
+    }
+}
+
+/// A fraction, not a slash.
+impl<T: Into<Expr>> ops::Div<T> for Expr {
+    type Output = Expr;
+    fn div(self, rhs: T) -> Self::Output {
+        Expr::frac(self, rhs)
+    }
+}
+
+impl ops::Neg for Expr {
+    type Output = Expr;
+    fn neg(self) -> Self::Output {
+        Expr::Neg(Box::new(self))
+    }
+}
+
+/// So that `2 * x` works, and not just `x * 2`. Only one integer type, or literals would be
+/// ambiguous.
+macro_rules! scalar_ops {
+    ($($t:ty),*) => {
+        $(
+            impl ops::Add<Expr> for $t {
+                type Output = Expr;
+                fn add(self, rhs: Expr) -> Self::Output {
+                    Expr::from(self) + rhs
+                }
+            }
+
+            impl ops::Sub<Expr> for $t {
+                type Output = Expr;
+                fn sub(self, rhs: Expr) -> Self::Output {
+                    Expr::from(self) - rhs
+                }
+            }
+
+            impl ops::Mul<Expr> for $t {
+                type Output = Expr;
+                fn mul(self, rhs: Expr) -> Self::Output {
+                    Expr::from(self) * rhs
+                }
+            }
+
+            impl ops::Div<Expr> for $t {
+                type Output = Expr;
+                fn div(self, rhs: Expr) -> Self::Output {
+                    Expr::from(self) / rhs
+                }
+            }
+        )*
+    };
+}
+
+scalar_ops!(i32, f64);
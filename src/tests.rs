@@ -22,7 +22,11 @@ fn primary() -> Null {
 #[test]
 fn secondary() -> Null {
     // let mut q = fs::File::create("tex/secondary.tex")?;
-    let mut q = fs::File::options().write(true).open("tex/secondary.tex")?;
+    let mut q = fs::File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open("tex/secondary.tex")?;
     let mut doc = Document::new(DocumentClass::new("book"));
     doc.set_md("title", &["author"]);
     let mut p1 = Package::new("parskip");
@@ -93,7 +97,11 @@ fn tertiary() -> Null {
 #[test]
 fn quaternary() -> Null {
     // let mut q = fs::File::create("tex/quaternary.tex")?;
-    let mut q = fs::File::options().write(true).open("tex/quaternary.tex")?;
+    let mut q = fs::File::options()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open("tex/quaternary.tex")?;
     let mut doc = Document::new(DocumentClass::new("book"));
     let mut p1 = Part::new("part1");
     p1.attach_vec(vec![Component::Chapter(Chapter::new("chap")); 5])?;
@@ -211,3 +219,1461 @@ fn tenth() -> Null {
 
     Ok(())
 }
+
+#[cfg(feature = "markdown")]
+#[test]
+fn markdown() -> Null {
+    use crate::markdown::MarkdownImporter;
+
+    let src = "# One\n\nSome *text* with `code_1`.\n\n## Two\n\n- a\n- b\n\n# Three\n\n| x | y |\n|---|---|\n| 1 | 2 |\n\n![plot](plot.png)\n";
+    let comps = MarkdownImporter::new().import(src)?;
+    assert_eq!(comps.len(), 2);
+    assert!(comps.iter().all(|x| matches!(x, Component::Section(_))));
+
+    let one = comps[0].to_string();
+    assert!(one.starts_with("\\section{One}"));
+    assert!(one.contains("\\textit{text}"));
+    assert!(one.contains("\\texttt{code\\_1}"));
+    assert!(one.contains("\\subsection{Two}"));
+    assert!(one.contains("\\item a"));
+
+    let three = comps[1].to_string();
+    assert!(three.contains("\\begin{tabular}{|l|l|}"));
+    assert!(three.contains("\\includegraphics{plot.png}"));
+
+    let comps = MarkdownImporter::with_top_rank(1)?.import("# C\n\n## S\n")?;
+    assert!(matches!(comps[0], Component::Chapter(_)));
+    assert!(comps[0].to_string().contains("\\section{S}"));
+    assert!(matches!(
+        MarkdownImporter::with_top_rank(4),
+        Err(TexError::InvalidOptions(_))
+    ));
+
+    let comps = MarkdownImporter::new().import("# The *best* `a_b`\n")?;
+    assert!(comps[0]
+        .to_string()
+        .starts_with("\\section{The \\textit{best}  \\texttt{a\\_b}}"));
+
+    let comps = MarkdownImporter::new().import("| a | b | c |\n|:-:|--:|:--|\n| 1 | 2 | 3 |\n")?;
+    assert!(comps[0].to_string().contains("\\begin{tabular}{|c|r|l|}"));
+
+    Ok(())
+}
+
+#[test]
+fn streaming() -> Null {
+    let mut doc = document!("article");
+    let mut sec = section!("one");
+    sec.attach(Component::Line(Line::with_components(vec![textchunk!(
+        "streamed", "bold"
+    )])))?
+    .attach(Component::Line(Line::new()))?;
+    doc.attach(sec)?;
+
+    let mut buf = vec![];
+    doc.write_to(&mut buf)?;
+    let streamed = String::from_utf8(buf)?;
+    assert_eq!(streamed, doc.to_string());
+    assert!(streamed.contains("\\section{one} \n \\textbf{streamed}  \\\\\n\n \n "));
+
+    let mut f = fs::File::create("tex/streaming.tex")?;
+    doc.write_to(&mut f)?;
+
+    Ok(())
+}
+
+/// A stand-in engine: counts its runs, and "compiles" by copying the .tex over to the .pdf.
+#[cfg(unix)]
+fn fake_engine(name: &str, body: &str) -> Result<String, Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = std::env::current_dir()?.join(format!("tex/{name}.sh"));
+    fs::write(&path, format!("#!/bin/sh\n{body}\n"))?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    Ok(path.to_str().unwrap().to_string())
+}
+
+#[cfg(unix)]
+#[test]
+fn compile() -> Null {
+    let engine = fake_engine(
+        "engine_ok",
+        "echo run >> runs.txt\ncp \"$1\" \"$(basename \"$1\" .tex).pdf\"",
+    )?;
+
+    let mut doc = document!("article");
+    doc.enable_graphicx("examples/img");
+    doc.attach(image!("ss.png"))?
+        .attach(figure!("ss", "bare name".to_string()))?;
+
+    let _ = fs::remove_dir_all("tex/build");
+    let pdf = Compiler::new(Engine::Custom(engine))
+        .set_build_dir("tex/build")
+        .set_passes(2)
+        .set_jobname("report")
+        .compile(&doc)?;
+
+    assert!(pdf.ends_with("report.pdf"));
+    assert_eq!(fs::read_to_string(&pdf)?, doc.to_string());
+    assert_eq!(fs::read_to_string("tex/build/runs.txt")?.lines().count(), 2);
+    assert!(fs::metadata("tex/build/ss.png")?.is_file());
+
+    doc.attach(image!("nope.png"))?;
+    assert!(matches!(
+        Compiler::new(Engine::Custom("true".to_string())).compile(&doc),
+        Err(TexError::MissingImage(s)) if s == "nope.png"
+    ));
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn compile_failure() -> Null {
+    let engine = fake_engine(
+        "engine_bad",
+        "echo \"! Undefined control sequence.\" > \"$(basename \"$1\" .tex).log\"\nexit 3",
+    )?;
+
+    let doc = document!("article");
+    match Compiler::new(Engine::Custom(engine)).compile(&doc) {
+        Err(TexError::CompileFailed(f)) => {
+            assert_eq!(f.pass, 1);
+            assert_eq!(f.status, Some(3));
+            assert!(f.log.contains("Undefined control sequence"));
+            assert_eq!(f.errors().count(), 1);
+            fs::remove_dir_all(f.build_dir)?;
+        }
+        other => panic!("{:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn source_map() -> Null {
+    let mut doc = document!("article");
+    doc.scratch();
+    let mut intro = section!("Intro");
+    intro.attach(textchunk!("hello"))?;
+    let mut results = section!("Results");
+    let mut list = List::new(ListType::Itemize);
+    list.attach(textchunk!("a"))?.attach(textchunk!("b"))?;
+    results.attach(Component::List(list))?;
+    doc.attach(intro)?.attach(results)?;
+
+    let mut buf = vec![];
+    let map = doc.write_mapped(&mut buf)?;
+    let out = String::from_utf8(buf)?;
+    assert_eq!(out, doc.to_string());
+
+    let line_of = |needle: &str| out.lines().position(|x| x.contains(needle)).unwrap() + 1;
+    let span = map.locate(line_of("\\item b")).unwrap();
+    assert_eq!(span.path, vec!["Section \"Results\"", "List"]);
+    let span = map.locate(line_of("\\section{Intro}")).unwrap();
+    assert_eq!(span.path, vec!["Section \"Intro\""]);
+    assert_eq!(map.spans().len(), 3);
+    assert!(map.locate(line_of("\\begin{document}")).is_none());
+
+    // Running out of room inside the list leaves nothing behind for the next one.
+    let mut small = vec![0u8; out.find("\\item b").unwrap()];
+    assert!(doc.write_mapped(&mut &mut small[..]).is_err());
+    let again = doc.write_mapped(&mut vec![])?;
+    assert_eq!(again.spans(), map.spans());
+
+    Ok(())
+}
+
+#[test]
+fn log_parsing() -> Null {
+    let log = "(./main.tex
+! Undefined control sequence.
+l.42 \\foo
+          {bar}
+! LaTeX Error: File `fancy.sty' not found.
+
+Type X to quit or <RETURN> to proceed,
+l.3 \\usepackage
+Overfull \\hbox (12.5pt too wide) in paragraph at lines 17--19
+Underfull \\vbox (badness 10000) detected at line 80
+LaTeX Warning: Reference `fig:f1' on page 1 undefined on input line 23.
+LaTeX Warning: Citation `knuth84' on page 2 undefined on input line 30.
+Package hyperref Warning: Token not allowed in a PDF string (Unicode):
+./main.tex:7: Missing $ inserted.
+";
+    let d = parse_log(log);
+    let kinds = d.iter().map(|x| x.kind.clone()).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            DiagnosticKind::Error,
+            DiagnosticKind::MissingPackage("fancy".to_string()),
+            DiagnosticKind::OverfullBox,
+            DiagnosticKind::UnderfullBox,
+            DiagnosticKind::UndefinedReference("fig:f1".to_string()),
+            DiagnosticKind::UndefinedCitation("knuth84".to_string()),
+            DiagnosticKind::Warning,
+            DiagnosticKind::Error,
+        ]
+    );
+    let lines = d.iter().map(|x| x.line).collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            Some(42),
+            Some(3),
+            Some(17),
+            Some(80),
+            Some(23),
+            Some(30),
+            None,
+            Some(7)
+        ]
+    );
+
+    // Lines wrapped at 79 characters are glued back together.
+    let wrapped = format!(
+        "LaTeX Warning: Reference `{}\n{}' on page 1 undefined on input line 5.",
+        "a".repeat(53),
+        "b"
+    );
+    let d = parse_log(&wrapped);
+    assert_eq!(
+        d[0].kind,
+        DiagnosticKind::UndefinedReference(format!("{}b", "a".repeat(53)))
+    );
+
+    // Unless the line is just that long, and the next one is a diagnostic of its own.
+    let full = format!("LaTeX Warning: {}", "x".repeat(64));
+    assert_eq!(full.len(), 79);
+    let d = parse_log(&format!(
+        "{}\n! Undefined control sequence.\nl.9 \\foo\n",
+        full
+    ));
+    assert_eq!(d.len(), 2);
+    assert_eq!(d[0].message, "x".repeat(64));
+    assert_eq!(d[1].kind, DiagnosticKind::Error);
+    assert_eq!(d[1].line, Some(9));
+
+    // Package warnings go on over several lines.
+    let d = parse_log(
+        "Package hyperref Warning: Token not allowed in a PDF string (Unicode):
+(hyperref)                removing `math shift' on input line 12.
+",
+    );
+    assert_eq!(d.len(), 1);
+    assert_eq!(
+        d[0].message,
+        "Token not allowed in a PDF string (Unicode): removing `math shift' on input line 12."
+    );
+    assert_eq!(d[0].line, Some(12));
+
+    Ok(())
+}
+
+#[test]
+fn label_validation() -> Null {
+    let mut doc = document!("article");
+    let mut s1 = section!("one");
+    s1.attach(label!("sec:one"))?
+        .attach(label!("fig:plot"))?
+        .attach(reference!("fig:plot"))?;
+    doc.new_component(s1);
+    assert!(doc.validate().is_ok());
+    assert_eq!(doc.to_string_checked()?, doc.to_string());
+
+    let mut s2 = section!("two");
+    s2.attach(label!("sec:one"))?
+        .attach(reference!("tab:plot"))?
+        .attach(reference!("eq:nowhere"))?;
+    doc.new_component(s2);
+
+    let problems = doc.problems();
+    assert!(matches!(&problems[0], TexError::LabelDuplicate(s) if s == "sec:one"));
+    assert!(
+        matches!(&problems[1], TexError::LabelMismatch(l, r) if l == "fig:plot" && r == "tab:plot")
+    );
+    assert!(matches!(&problems[2], TexError::LabelUndefined(s) if s == "eq:nowhere"));
+    assert!(matches!(doc.validate(), Err(TexError::Invalid(v)) if v.len() == 3));
+    assert!(doc.write_checked(&mut vec![]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn bibliography() -> Null {
+    let bib = Bibliography::from_bib(
+        BibBackend::Inline,
+        r#"
+        @string{acm = "ACM"}
+        @comment{ ignored }
+        @Article{knuth84,
+          author = {Donald E. Knuth},
+          title = "Literate {P}rogramming",
+          journal = acm # " Computing Surveys",
+          year = 1984,
+          month = may,
+        }
+        @book(lamport94, author = {Leslie Lamport}, title = {\LaTeX}, year = {1994})
+        "#,
+    )?;
+    let knuth = bib.get("knuth84").unwrap();
+    assert_eq!(knuth.typ(), &EntryType::Article);
+    assert_eq!(knuth.get("title"), Some("Literate {P}rogramming"));
+    assert_eq!(knuth.get("journal"), Some("ACM Computing Surveys"));
+    assert_eq!(knuth.get("month"), Some("May"));
+    assert_eq!(bib.entries().len(), 2);
+
+    // Round trip.
+    let mut buf = vec![];
+    bib.write_bib(&mut buf)?;
+    assert_eq!(parse_bib(&String::from_utf8(buf)?)?, bib.entries().to_vec());
+    assert!(matches!(
+        parse_bib("@article{x, title = }"),
+        Err(TexError::BibError(_))
+    ));
+
+    let mut doc = document!("article");
+    doc.set_bibliography(bib.clone());
+    doc.attach(cite!("knuth84"))?
+        .attach(cite!(parencite; "knuth84", "nobody"))?;
+    let s = doc.to_string();
+    assert!(s.contains("\\begin{thebibliography}{9}"));
+    assert!(s.contains("\\bibitem{knuth84}"));
+    assert!(!s.contains("\\bibitem{lamport94}"));
+    assert!(s.contains("\\providecommand{\\parencite}{\\cite}"));
+    assert!(s.contains("~\\parencite{knuth84,nobody}"));
+    assert!(matches!(&doc.problems()[..], [TexError::CitationUndefined(k)] if k == "nobody"));
+
+    // Nothing cited, nothing to list.
+    let mut doc = document!("article");
+    doc.set_bibliography(bib.clone());
+    doc.attach(textchunk!("No citations here.", "normal"))?;
+    assert!(!doc.to_string().contains("thebibliography"));
+
+    let mut doc = document!("article");
+    let mut bib = bib;
+    bib.add_entry(BibEntry::misc("lamport94", &["L. Lamport"], "Again"));
+    doc.set_bibliography(Bibliography::from_bib(
+        BibBackend::Biblatex {
+            file: "refs".to_string(),
+            style: "authoryear".to_string(),
+        },
+        "",
+    )?);
+    doc.bibliography_mut()
+        .unwrap()
+        .add_entry(bib.entries()[0].clone());
+    doc.attach(cite!(textcite; "knuth84"))?;
+    assert!(matches!(
+        CiteKind::try_from("citep "),
+        Err(TexError::VariantUndefined)
+    ));
+    let s = doc.to_string();
+    assert!(s.contains("\\usepackage[style=authoryear, ]{biblatex}"));
+    assert!(s.contains("\\addbibresource{refs.bib}"));
+    assert!(s.contains("\\printbibliography"));
+    assert!(doc.validate().is_ok());
+    assert!(matches!(
+        &check_citations(Some(&bib), &[])[..],
+        [TexError::CitationDuplicate(k)] if k == "lamport94"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn math() -> Null {
+    let x = Expr::var("x");
+    let y = Expr::var("y");
+
+    // Only the brackets that are needed.
+    assert_eq!((x.clone() + y.clone() * 2).to_string(), "x + y \\cdot 2");
+    assert_eq!(
+        ((x.clone() + y.clone()) * 2).to_string(),
+        "\\left(x + y\\right) \\cdot 2"
+    );
+    assert_eq!(
+        (x.clone() - (y.clone() - 1)).to_string(),
+        "x - \\left(y - 1\\right)"
+    );
+    assert_eq!((x.clone() + (y.clone() + 1)).to_string(), "x + y + 1");
+    assert_eq!((x.clone() - -3).to_string(), "x - \\left(-3\\right)");
+    assert_eq!((-(x.clone() + 1)).to_string(), "-\\left(x + 1\\right)");
+    assert_eq!(
+        (2 * x.clone()).pow(2).to_string(),
+        "\\left(2 \\cdot x\\right)^{2}"
+    );
+    assert_eq!(Expr::from(-1).pow("n").to_string(), "\\left(-1\\right)^{n}");
+    assert_eq!(
+        (x.clone() / 2).pow(2).to_string(),
+        "\\left(\\frac{x}{2}\\right)^{2}"
+    );
+    assert_eq!(x.clone().subscript("i").pow(2).to_string(), "x_{i}^{2}");
+
+    let sum = Expr::sum(
+        Some(Expr::var("i").equals(1)),
+        Some(Expr::var("n")),
+        x.clone().subscript("i") + 1,
+    );
+    assert_eq!(
+        sum.to_string(),
+        "\\sum_{i = 1}^{n} \\left(x_{i} + 1\\right)"
+    );
+    assert_eq!(
+        (sum.clone() * y.clone()).to_string(),
+        "\\left(\\sum_{i = 1}^{n} \\left(x_{i} + 1\\right)\\right) \\cdot y"
+    );
+    assert_eq!(
+        Expr::integral(
+            Some(0.into()),
+            Some(Expr::raw("\\infty")),
+            Expr::apply("exp", vec![-x.clone()]),
+            "x"
+        )
+        .to_string(),
+        "\\int_{0}^{\\infty} \\exp\\left(-x\\right) \\,dx"
+    );
+    assert_eq!(
+        Expr::limit("x", 0, Expr::apply("sin", vec![x.clone()]) / x.clone()).to_string(),
+        "\\lim_{x \\to 0} \\frac{\\sin\\left(x\\right)}{x}"
+    );
+    assert_eq!(
+        Expr::matrix(
+            MatrixKind::Paren,
+            vec![
+                vec![1.into(), 0.into()],
+                vec![0.into(), Expr::root(3, y.clone())]
+            ]
+        )
+        .to_string(),
+        "\\begin{pmatrix} 1 & 0 \\\\ 0 & \\sqrt[3]{y} \\end{pmatrix}"
+    );
+    assert_eq!(
+        Expr::apply("f", vec![x.clone(), y.clone()]).to_string(),
+        "f\\left(x, y\\right)"
+    );
+    assert_eq!(
+        Expr::apply("rank", vec![x.clone()]).to_string(),
+        "\\operatorname{rank}\\left(x\\right)"
+    );
+
+    let mut p = Paragraph::new();
+    p.attach((x.clone() * 0.5).into())?
+        .attach(Formula::display(x.clone().equals(1)).into())?
+        .attach(builtin!(BuiltinType::Sin((x + 1).into())))?;
+    assert!(Component::Paragraph(p)
+        .to_string()
+        .contains("\\(x \\cdot 0.5\\)\\[x = 1\\]\\sin{x + 1 } "));
+
+    Ok(())
+}
+
+#[test]
+fn equations() -> Null {
+    let x = Expr::var("x");
+    let y = Expr::var("y");
+
+    let mut align = Equation::new(EquationKind::Align);
+    let mut first = EquationRow::relation(y.clone(), BinOp::Eq, (x.clone() + 1).pow(2));
+    first.set_label(Label::Equation("expand".to_string()));
+    let mut second = EquationRow::relation(
+        Expr::raw(""),
+        BinOp::Eq,
+        x.clone().pow(2) + 2 * x.clone() + 1,
+    );
+    second.nonumber();
+    align.add_row(first)?.add_row(second)?;
+    assert_eq!(
+        align.to_string(),
+        "\\begin{align}\ny & = \\left(x + 1\\right)^{2} \\label{eq:expand} \\\\\n & = x^{2} + 2 \\cdot x + 1 \\nonumber\n\\end{align}\n"
+    );
+
+    let mut gather = Equation::unnumbered(EquationKind::Gather);
+    gather.add_row(x.clone().equals(1).into())?;
+    let mut tagged = EquationRow::from(y.clone().equals(2));
+    tagged.set_numbering(Numbering::Tag("*".to_string()));
+    gather.add_row(tagged)?;
+    assert!(gather
+        .to_string()
+        .starts_with("\\begin{gather*}\nx = 1 \\\\\ny = 2 \\tag{*}\n"));
+
+    // No alignment points outside align, and just the one line in equation.
+    assert!(matches!(
+        gather.add_row(EquationRow::relation(x.clone(), BinOp::Lt, y.clone())),
+        Err(TexError::EquationShape(env, _)) if env == "gather"
+    ));
+    let mut single = Equation::single(x.clone().equals(y.clone()));
+    assert!(matches!(
+        single.add_row(x.clone().into()),
+        Err(TexError::EquationShape(..))
+    ));
+
+    // multline has the one number, so the one label.
+    let mut multline = Equation::new(EquationKind::Multline);
+    let mut top = EquationRow::from(y.clone().equals(x.clone()));
+    top.set_label(Label::Equation("top".to_string()));
+    multline.add_row(top.clone())?;
+    assert!(matches!(
+        multline.add_row(top),
+        Err(TexError::EquationShape(env, _)) if env == "multline"
+    ));
+
+    // Labels on lines without a number.
+    let mut lost = EquationRow::from(x.clone().equals(2));
+    lost.set_label(Label::Equation("lost".to_string()));
+    let mut starred = Equation::unnumbered(EquationKind::Gather);
+    starred.add_row(lost.clone())?;
+    let mut skipped = Equation::new(EquationKind::Gather);
+    skipped.add_row(
+        lost.set_label(Label::Equation("skipped".to_string()))
+            .nonumber()
+            .clone(),
+    )?;
+    let mut tagged = EquationRow::from(y.clone().equals(3));
+    tagged
+        .set_label(Label::Equation("tagged".to_string()))
+        .set_numbering(Numbering::Tag("A".to_string()));
+    starred.add_row(tagged)?;
+    let mut doc = document!("article");
+    doc.attach(starred.into())?.attach(skipped.into())?;
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::LabelUnnumbered(a), TexError::LabelUnnumbered(b)] if a == "eq:lost" && b == "eq:skipped"
+    ));
+
+    let mut doc = document!("article");
+    assert!(!doc.to_string().contains("amsmath"));
+    doc.attach(align.into())?.attach(reference!("eq:expand"))?;
+    assert!(doc.to_string().contains("\\usepackage[]{amsmath}"));
+    assert!(doc.validate().is_ok());
+    doc.new_package(package!("amsmath", "fleqn"));
+    assert_eq!(doc.to_string().matches("{amsmath}").count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn package_inference() -> Null {
+    let mut doc = document!("article");
+    let mut p = Paragraph::new();
+    p.attach(textchunk!("gone", "strikethrough"))?
+        .attach(textchunk!("R", "mathbb"))?
+        .attach(Expr::apply("rank", vec![Expr::var("A")]).into())?;
+    doc.attach(Component::Paragraph(p))?;
+    doc.new_package(package!("ulem", "normalem"));
+
+    let names = doc
+        .packages()?
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec!["graphicx", "ulem", "amssymb", "amsmath", "hyperref"]
+    );
+    let s = doc.to_string();
+    assert_eq!(s.matches("{ulem}").count(), 1);
+    assert!(s.contains("\\usepackage[normalem, ]{ulem}"));
+
+    // Options are merged, and clashing key=value options are an error.
+    let merged = merge_packages(vec![
+        package!("geometry", "margin=1in"),
+        package!("geometry", "landscape"),
+    ])?;
+    assert_eq!(merged[0].options(), ["margin=1in", "landscape"]);
+    assert!(merge_packages(vec![
+        package!("geometry", "margin=1in"),
+        package!("geometry", "margin=2cm"),
+    ])
+    .is_err());
+
+    doc.new_package(package!("biblatex", "style=numeric"));
+    doc.set_bibliography(Bibliography::new(BibBackend::Biblatex {
+        file: "refs".to_string(),
+        style: "authoryear".to_string(),
+    }));
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::PackageConflict(p, _, _)] if p == "biblatex"
+    ));
+    // It still renders, with the first value given.
+    assert!(doc
+        .to_string()
+        .contains("\\usepackage[style=numeric, ]{biblatex}"));
+    assert!(doc.write_checked(&mut vec![]).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn preamble() -> Null {
+    let mut doc = document!("article");
+    doc.new_package(package!("cleveref"));
+    doc.enable_graphicx("examples/img");
+    doc.enable_hyperref();
+    doc.new_package(package!("hyperref", "colorlinks"));
+    doc.new_package(package!("xcolor"));
+
+    let names = doc
+        .packages()?
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["graphicx", "xcolor", "hyperref", "cleveref"]);
+    assert!(doc
+        .to_string()
+        .contains("\\usepackage[colorlinks, ]{hyperref}"));
+
+    // Dependencies first, then insertion order. Redefining keeps the original spot.
+    doc.new_command(Command::new("pair", 2, "\\brak{#1, #2}"));
+    doc.new_command(Command::new("norm", 1, "\\|#1\\|"));
+    doc.new_command(Command::new("brak", 1, "\\left(#1\\right)"));
+    doc.new_command(Command::new("brakk", 1, "\\left[#1\\right]"));
+    doc.new_command(Command::new("norm", 1, "\\lVert #1\\rVert"));
+    let order = doc
+        .preamble()
+        .commands()
+        .iter()
+        .map(|x| x.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec!["brak", "pair", "norm", "brakk"]);
+    assert_eq!(doc.get_command("norm")?.def, "\\lVert #1\\rVert");
+    assert_eq!(doc.to_string(), doc.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn tables() -> Null {
+    // The old output is untouched.
+    let h = row!("a", "b");
+    let t = Table::new(2, h);
+    assert_eq!(
+        t.to_string(),
+        "\\begin{tabular}{|c|c|} \n \\hline \n a  & b  \\\\ \n \n \\hline \n  \\hline \\end{tabular} "
+    );
+
+    let mut t = Table::with_columns(
+        ColumnSpec::parse("l p{3cm} S")?,
+        row!("Item", "Notes", "Amount"),
+    );
+    t.set_style(TableStyle::Booktabs);
+    let mut total = Cell::new(textchunk!("Total", "bold"));
+    total.span_columns(2, ColumnSpec::Right);
+    let mut sum = Row::with_cells(vec![total.into(), textchunk!("42.5", "normal")]);
+    sum.add_rule(Rule::CMidRule(3, 3));
+    let mut tall = Cell::new(textchunk!("Rent", "normal"));
+    tall.span_rows(2);
+    t.attach(Component::Row(Row::with_cells(vec![
+        tall.into(),
+        textchunk!("Jan", "normal"),
+        textchunk!("20", "normal"),
+    ])))?
+    .attach(Component::Row(row!("", "Feb", "22.5")))?
+    .attach(Component::Row(sum))?;
+
+    assert_eq!(
+        t.to_string(),
+        "\\begin{tabular}{lp{3cm}S}\n\\toprule\nItem  & Notes  & Amount  \\\\ \n\\midrule\n\
+         \\multirow{2}{*}{Rent } & Jan  & 20  \\\\ \n  & Feb  & 22.5  \\\\ \n\
+         \\multicolumn{2}{r}{\\textbf{Total} } & 42.5  \\\\ \n\\cmidrule{3-3}\n\\bottomrule\n\\end{tabular}\n"
+    );
+    assert!(t.check().is_empty());
+
+    let mut doc = document!("article");
+    doc.attach(Component::Table(t.clone()))?;
+    let names = doc
+        .packages()?
+        .iter()
+        .map(|x| x.name().to_string())
+        .collect::<Vec<_>>();
+    for p in ["booktabs", "siunitx", "multirow"] {
+        assert!(names.contains(&p.to_string()));
+    }
+
+    t.attach(Component::Row(row!("too", "short")))?;
+    assert!(matches!(&t.check()[..], [TexError::TableWidth(4, 3, 2)]));
+    doc.attach(Component::Table(t))?;
+    assert!(doc.validate().is_err());
+
+    assert!(matches!(
+        ColumnSpec::parse("lx"),
+        Err(TexError::InvalidOptions(_))
+    ));
+    assert!(matches!(
+        ColumnSpec::parse("lp"),
+        Err(TexError::InvalidOptions(_))
+    ));
+    assert!(matches!(
+        Table::new(2, Row::new()).set_columns(vec![ColumnSpec::Left]),
+        Err(TexError::ColumnCount(2, 1))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn table_float() -> Null {
+    let mut t = Table::with_columns(ColumnSpec::parse("lr")?, row!("Year", "Revenue"));
+    t.set_style(TableStyle::Booktabs)
+        .attach(Component::Row(row!("2023", "1.2")))?;
+    let mut float = TableFloat::new(t, "Revenue by year, in millions");
+    float.set_short_caption("Revenue").set_label("revenue");
+    float.add_option("t");
+    float.add_option("!");
+
+    assert_eq!(
+        float.to_string(),
+        "\\begin{table}[t!]\n\\centering\n\\caption[Revenue]{Revenue by year, in millions}\n \\label{tab:revenue} \n\
+         \\begin{tabular}{lr}\n\\toprule\nYear  & Revenue  \\\\ \n\\midrule\n2023  & 1.2  \\\\ \n\\bottomrule\n\\end{tabular}\n\
+         \n\\end{table}\n"
+    );
+
+    let mut doc = document!("article");
+    doc.attach(float.into())?
+        .attach(reference!("tab:revenue"))?;
+    assert!(doc.validate().is_ok());
+    assert!(doc.to_string().contains("{booktabs}"));
+
+    doc.attach(reference!("fig:revenue"))?;
+    assert!(matches!(&doc.problems()[..], [TexError::LabelMismatch(..)]));
+
+    // The table inside is still checked.
+    let mut short = Table::new(2, row!("a", "b"));
+    short.attach(Component::Row(row!("c")))?;
+    let mut doc = document!("article");
+    doc.attach(TableFloat::new(short, "Short").into())?;
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::TableWidth(1, 2, 1)]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn data_tables() -> Null {
+    let t = Table::from_data(
+        &["Name", "Share"],
+        vec![vec!["A&B", "0.25"], vec!["C_D", "x"]],
+    )?;
+    assert!(t.to_string().contains("A\\&B  & 0.25  \\\\"));
+    assert!(t.to_string().contains("C\\_D  & x  \\\\"));
+    let t = Table::from_data(&["Path"], vec![vec!["C:\\tmp\\~me 2^3"]])?;
+    assert!(t.to_string().contains(
+        "C:\\textbackslash{}tmp\\textbackslash{}\\textasciitilde{}me 2\\textasciicircum{}3  \\\\"
+    ));
+    assert!(matches!(
+        Table::from_data(&["a", "b"], vec![vec![1, 2], vec![3]]),
+        Err(TexError::TableWidth(2, 2, 1))
+    ));
+
+    let mut t = Table::from_data(
+        &["Year", "Revenue", "Growth"],
+        vec![
+            vec![
+                "2022".to_string(),
+                "1234567.891".to_string(),
+                "0.0712".to_string(),
+            ],
+            vec!["2023".to_string(), "-1000".to_string(), "n/a".to_string()],
+            vec!["2024".to_string(), "NaN".to_string(), "inf".to_string()],
+            vec!["2025".to_string(), "1e999".to_string(), "0".to_string()],
+        ],
+    )?;
+    t.format_numbers(1, NumberFormat::Grouped(2))
+        .format_numbers(2, NumberFormat::Percent(1));
+    let s = t.to_string();
+    assert!(s.starts_with("\\begin{tabular}{|c|r|r|}"));
+    assert!(s.contains("2022  & 1,234,567.89  & 7.1\\%"));
+    assert!(s.contains("2023  & -1,000.00  & n/a"));
+    assert!(s.contains("2024  & NaN  & inf  \\\\"));
+    assert!(s.contains("2025  & 1e999  & 0.0\\%"));
+    assert_eq!(NumberFormat::Fixed(1).format(2.25), "2.2");
+
+    struct Sale {
+        region: &'static str,
+        amount: f64,
+    }
+    impl TableRow for Sale {
+        fn columns() -> Vec<String> {
+            vec!["Region".to_string(), "Amount".to_string()]
+        }
+        fn cells(&self) -> Vec<String> {
+            vec![self.region.to_string(), self.amount.to_string()]
+        }
+    }
+    let sales = [
+        Sale {
+            region: "North",
+            amount: 10.0,
+        },
+        Sale {
+            region: "50%",
+            amount: 2.5,
+        },
+    ];
+    let mut t = Table::from_records(&sales)?;
+    t.format_numbers(1, NumberFormat::Fixed(2));
+    let s = t.to_string();
+    assert!(s.contains("Region  & Amount  \\\\"));
+    assert!(s.contains("50\\%  & 2.50  \\\\"));
+    assert!(t.check().is_empty());
+
+    struct Short;
+    impl TableRow for Short {
+        fn columns() -> Vec<String> {
+            vec!["a".to_string(), "b".to_string()]
+        }
+        fn cells(&self) -> Vec<String> {
+            vec!["1".to_string()]
+        }
+    }
+    assert!(matches!(
+        Table::from_records([Short]),
+        Err(TexError::TableWidth(1, 2, 1))
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_tables() -> Null {
+    let t =
+        Table::from_csv("city,population\n\"Paris, FR\",2102650\nSão Paulo,11451245\n".as_bytes())?;
+    let s = t.to_string();
+    assert!(s.contains("city  & population  \\\\"));
+    assert!(s.contains("Paris, FR  & 2102650  \\\\"));
+    assert!(t.check().is_empty());
+    assert!(matches!(
+        Table::from_csv("a,b\n1\n".as_bytes()),
+        Err(TexError::CsvError(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn long_table() -> Null {
+    let mut t = Table::with_columns(ColumnSpec::parse("lr")?, row!("Year", "Revenue"));
+    let mut long = LongTable::new();
+    long.set_caption("Revenue by year")
+        .set_label("revenue")
+        .set_continued("Continued on next page")
+        .set_last_foot(row!("Total", "3.3"));
+    t.set_style(TableStyle::Booktabs)
+        .set_long(long)
+        .attach(Component::Row(row!("2023", "1.2")))?;
+    assert_eq!(
+        t.to_string(),
+        "\\begin{longtable}{lr}\n\\caption{Revenue by year}\\label{tab:revenue} \\\\\n\
+         \\toprule\nYear  & Revenue  \\\\ \n\\midrule\n\\endfirsthead\n\
+         \\caption[]{(continued)} \\\\\n\\toprule\nYear  & Revenue  \\\\ \n\\midrule\n\\endhead\n\
+         \\midrule\n\\multicolumn{2}{r}{Continued on next page} \\\\\n\\endfoot\n\
+         \\midrule\nTotal  & 3.3  \\\\ \n\\bottomrule\n\\endlastfoot\n\
+         2023  & 1.2  \\\\ \n\\end{longtable}\n"
+    );
+
+    let mut doc = document!("article");
+    doc.attach(t.clone().into())?
+        .attach(reference!("tab:revenue"))?;
+    assert!(doc.validate().is_ok());
+    assert!(doc.to_string().contains("\\usepackage[]{longtable}"));
+
+    doc.attach(TableFloat::new(t, "Twice").into())?;
+    assert!(doc
+        .problems()
+        .iter()
+        .any(|x| matches!(x, TexError::LabelDuplicate(_))));
+    assert!(doc
+        .problems()
+        .iter()
+        .any(|x| matches!(x, TexError::LongTableInFloat)));
+
+    // A label without a caption has no number to refer to.
+    let mut t = Table::new(1, row!("x"));
+    let mut long = LongTable::new();
+    long.set_label("uncaptioned");
+    t.set_long(long);
+    assert!(matches!(&t.check()[..], [TexError::InvalidOptions(_)]));
+    let mut doc = document!("article");
+    doc.attach(t.into())?
+        .attach(reference!("tab:uncaptioned"))?;
+    assert!(doc.validate().is_err());
+
+    // No header, no header row.
+    let mut t = Table::new(1, Row::with_cells(vec![]));
+    t.set_style(TableStyle::Booktabs)
+        .set_long(LongTable::new())
+        .attach(Component::Row(row!("x")))?;
+    assert!(t
+        .to_string()
+        .starts_with("\\begin{longtable}{c}\n\\toprule\n\\endfirsthead\n\\toprule\n\\endhead\n"));
+
+    Ok(())
+}
+
+#[test]
+fn typed_options() -> Null {
+    let mut img = Image::new("plot.png");
+    img.set_options(
+        ImageOptions::new()
+            .width(Length::TextWidth(0.5))
+            .angle(90.0)
+            .trim(
+                Length::Cm(1.0),
+                Length::Pt(0.0),
+                Length::Cm(1.0),
+                Length::Pt(0.0),
+            )
+            .clip(true),
+    )?;
+    assert_eq!(
+        img.to_string(),
+        "\\includegraphics[trim=1cm 0pt 1cm 0pt, clip, angle=90, width=0.5\\textwidth]{plot.png} \n"
+    );
+    assert!(matches!(
+        Image::new("a.png").set_options(ImageOptions::new().scale(0.5).height(Length::Cm(3.0))),
+        Err(TexError::InvalidOptions(_))
+    ));
+    assert!(ImageOptions::new().clip(true).check().is_err());
+    assert!(ImageOptions::new()
+        .width(Length::Cm(f64::NAN))
+        .check()
+        .is_err());
+    assert!(ImageOptions::new().scale(f64::INFINITY).check().is_err());
+    assert!(SubFigure::new(Image::new("a.png"), "A")
+        .set_width(Length::TextWidth(f64::NAN))
+        .is_err());
+
+    let mut img = Image::new("a.png");
+    img.set_options(ImageOptions::new().width(Length::Cm(3.0)))?;
+    img.set_options(ImageOptions::new().width(Length::Cm(5.0)))?;
+    img.add_option("clip");
+    img.add_option("clip");
+    assert_eq!(
+        img.to_string(),
+        "\\includegraphics[width=5cm, clip]{a.png} \n"
+    );
+    assert_eq!(
+        Image::new("a.png").to_string(),
+        "\\includegraphics{a.png} \n"
+    );
+
+    assert_eq!(
+        Placement::parse("ht!")?,
+        [Placement::Here, Placement::Top, Placement::Force]
+    );
+    assert!(Placement::parse("hH").is_err());
+    assert!(Placement::parse("!").is_err());
+    assert!(Placement::parse("hh").is_err());
+    assert!(Placement::parse("x").is_err());
+
+    let mut fig = Figure::from_img(img, "A plot".to_string());
+    fig.set_placement(&Placement::parse("H")?)?;
+    assert!(fig.to_string().starts_with("\\begin{figure}[H]"));
+    assert_eq!(fig.placement(), [Placement::Exact]);
+    let mut doc = document!("article");
+    doc.attach(fig.into())?;
+    assert!(doc.to_string().contains("\\usepackage[]{float}"));
+
+    // Free-form placement goes through the same specifiers.
+    let mut fig = Figure::new("plot.png", "A plot".to_string());
+    fig.add_option("ht");
+    fig.add_option("h");
+    assert!(fig.to_string().starts_with("\\begin{figure}[ht]"));
+    fig.add_option("H");
+    fig.add_option("x");
+    let mut float = TableFloat::new(Table::new(1, row!("x")), "Table");
+    float.add_option("H");
+    let mut doc = document!("article");
+    doc.attach(fig.into())?.attach(float.into())?;
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::InvalidOptions(x), TexError::InvalidOptions(_)] if x.contains("x")
+    ));
+
+    let mut list = List::new(ListType::Enumerate);
+    list.add_key_value("label", "(\\roman*)");
+    list.add_key_value("label", "(\\alph*)");
+    list.add_option("nosep");
+    list.add_option("nosep");
+    assert!(list
+        .to_string()
+        .starts_with("\\begin{enumerate}[label=(\\alph*), nosep]"));
+
+    Ok(())
+}
+
+#[test]
+fn subfigures() -> Null {
+    let mut grid = MultiFigure::new(2, "Loss curves");
+    grid.set_label("loss");
+    for name in ["a", "b", "c"] {
+        let mut sub = SubFigure::new(Image::new(&format!("{}.png", name)), name);
+        sub.set_label(name);
+        grid.add_subfigure(sub);
+    }
+    assert_eq!(grid.width(), Length::LineWidth(0.49));
+    let s = grid.to_string();
+    assert!(s.starts_with(
+        "\\begin{figure}\n\\centering\n\\begin{subfigure}[b]{0.49\\linewidth}\n\\centering\n\
+         \\includegraphics[width=\\linewidth]{a.png}\n\\caption{a}\n \\label{fig:a} \n\\end{subfigure}\n\\hfill\n"
+    ));
+    // The third panel starts a new row.
+    assert!(s.contains(
+        "\\end{subfigure}\n\n\\begin{subfigure}[b]{0.49\\linewidth}\n\\centering\n\
+         \\includegraphics[width=\\linewidth]{c.png}"
+    ));
+    assert!(s.ends_with("\\caption{Loss curves}\n \\label{fig:loss} \n\\end{figure}\n"));
+
+    let mut doc = document!("article");
+    doc.attach(grid.into())?
+        .attach(reference!("fig:b"))?
+        .attach(reference!("fig:loss"))?;
+    assert!(doc.validate().is_ok());
+    assert!(doc.to_string().contains("\\usepackage[]{subcaption}"));
+    assert_eq!(doc.images().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn figure_labels() -> Null {
+    let plain = Figure::new("plot.png", "A plot".to_string());
+    assert_eq!(
+        plain.to_string(),
+        "\\begin{figure} \n \\centering \n \\includegraphics{plot.png} \n \n \\caption{A plot} \n \\end{figure} "
+    );
+
+    let mut fig = Figure::new("plot.png", "A plot of everything we measured".to_string());
+    fig.set_short_caption("A plot").set_label("plot");
+    assert!(fig.to_string().ends_with(
+        "\\caption[A plot]{A plot of everything we measured} \n  \\label{fig:plot} \n\\end{figure} "
+    ));
+    fig.set_caption_position(CaptionPosition::Above);
+    assert!(fig.to_string().starts_with(
+        "\\begin{figure} \n \\centering \n \\caption[A plot]{A plot of everything we measured} \n  \\label{fig:plot} \n\\includegraphics"
+    ));
+
+    let mut doc = document!("article");
+    doc.attach(fig.into())?.attach(reference!("fig:plot"))?;
+    assert!(doc.validate().is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn theorems() -> Null {
+    let mut doc = document!("article");
+    for kind in TheoremKind::defaults() {
+        doc.new_theorem(kind)?;
+    }
+    let mut claim = TheoremKind::new("claim", "Claim");
+    claim.set_counter(TheoremCounter::SharedWith("nope".to_string()));
+    assert!(matches!(doc.new_theorem(claim), Err(TexError::TheoremUndefined(s)) if s == "nope"));
+    assert!(matches!(
+        doc.get_theorem("conjecture"),
+        Err(TexError::TheoremUndefined(_))
+    ));
+
+    let mut lemma = theorem!(doc, "lemma");
+    let mut proof = Proof::new();
+    proof.attach(textchunk!("Obvious.", "normal"))?;
+    lemma
+        .set_title("Zorn")
+        .set_label("zorn")
+        .set_proof(proof)
+        .attach(textchunk!("Every chain has an upper bound.", "normal"))?;
+    assert_eq!(
+        lemma.to_string(),
+        "\\begin{lemma}[Zorn]\n \\label{thm:zorn} \nEvery chain has an upper bound. \n\\end{lemma}\n\
+         \\begin{proof}\nObvious. \n\\end{proof}\n"
+    );
+
+    doc.attach(lemma.into())?.attach(reference!("thm:zorn"))?;
+    assert!(doc.validate().is_ok());
+    let s = doc.to_string();
+    assert!(s.contains(
+        "\\theoremstyle{plain}\n\\newtheorem{theorem}{Theorem}\n\\newtheorem{lemma}[theorem]{Lemma}\n\
+         \\newtheorem{corollary}[theorem]{Corollary}\n\\theoremstyle{definition}\n\
+         \\newtheorem{definition}{Definition}\n\\theoremstyle{remark}\n\\newtheorem{remark}{Remark}\n"
+    ));
+    assert!(s.contains("\\usepackage[]{amsthm}"));
+
+    // Through Component, too.
+    let mut thm = Component::from(Theorem::new(&doc.get_theorem("theorem")?));
+    thm.attach_vec(vec![
+        textchunk!("One.", "normal"),
+        textchunk!("Two.", "normal"),
+    ])?;
+    assert!(thm.to_string().contains("One. Two. "));
+    let mut proof = Component::from(Proof::new());
+    proof.attach_vec(vec![textchunk!("Trivial.", "normal")])?;
+    assert!(proof.to_string().contains("Trivial. "));
+
+    let mut other = document!("article");
+    other.attach(Theorem::new(&TheoremKind::new("axiom", "Axiom")).into())?;
+    assert!(matches!(
+        &other.problems()[..],
+        [TexError::TheoremUndefined(_)]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn code_listings() -> Null {
+    let code = "fn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n";
+    let mut listing = CodeListing::new(code);
+    listing
+        .set_language("rust")
+        .set_line_numbers(true)
+        .set_caption("Hello")
+        .set_label("main")
+        .set_range(2, 3)?;
+    // listings doesn't know rust.
+    assert_eq!(
+        listing.to_string(),
+        "\\begin{lstlisting}[numbers=left, firstline=2, lastline=3, firstnumber=2, caption={Hello}, label={lst:main}]\n\
+         fn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n\\end{lstlisting}\n"
+    );
+
+    listing
+        .set_backend(ListingBackend::Minted)
+        .set_highlight(&[3]);
+    assert_eq!(
+        listing.to_string(),
+        "\\begin{listing}\n\\begin{minted}[linenos, firstline=2, lastline=3, firstnumber=2, highlightlines={3}]{rust}\n\
+         fn main() {\n    let x = 1;\n    println!(\"{x}\");\n}\n\\end{minted}\n\\caption{Hello}\n \\label{lst:main} \n\\end{listing}\n"
+    );
+    assert!(listing.check().is_empty());
+    listing.set_highlight(&[1]);
+    assert_eq!(listing.check().len(), 1);
+    assert!(listing.set_range(3, 2).is_err());
+
+    assert_eq!(detect_language("src/main.PY"), Some("python"));
+    let mut py = CodeListing::new("print(1)");
+    py.set_language("python");
+    assert_eq!(py.language(), Some("Python"));
+    py.set_highlight(&[1]);
+    assert!(matches!(&py.check()[..], [TexError::InvalidOptions(_)]));
+
+    let mut uncaptioned = CodeListing::new("x = 1");
+    uncaptioned.set_label("x");
+    assert!(
+        matches!(&uncaptioned.check()[..], [TexError::InvalidOptions(x)] if x.contains("lst:x"))
+    );
+
+    let listing = CodeListing::from_file("examples/collate.rs")?;
+    assert_eq!(listing.backend(), ListingBackend::Listings);
+    let mut doc = document!("article");
+    doc.attach(listing.into())?.attach(py.into())?;
+    assert!(doc.to_string().contains("\\usepackage[]{listings}"));
+    assert_eq!(doc.problems().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn algorithms() -> Null {
+    let mut alg = Algorithm::new("Euclid's algorithm");
+    alg.set_label("euclid")
+        .set_line_numbers(true)
+        .add_step(Step::function(
+            "Euclid",
+            "$a, b$",
+            vec![
+                Step::comment("The g.c.d. of a and b"),
+                Step::while_do(
+                    "$b \\neq 0$",
+                    vec![
+                        Step::if_then("$a > b$", vec![Step::state("$a \\gets a - b$")])
+                            .otherwise(vec![Step::state("$b \\gets b - a$")]),
+                    ],
+                ),
+                Step::ret("$a$"),
+            ],
+        ));
+    assert_eq!(
+        alg.to_string(),
+        "\\begin{algorithm}\n\\caption{Euclid's algorithm}\n \\label{alg:euclid} \n\\begin{algorithmic}[1]\n\
+         \x20 \\Function{Euclid}{$a, b$}\n\
+         \x20   \\State \\Comment{The g.c.d. of a and b}\n\
+         \x20   \\While{$b \\neq 0$}\n\
+         \x20     \\If{$a > b$}\n\
+         \x20       \\State $a \\gets a - b$\n\
+         \x20     \\Else\n\
+         \x20       \\State $b \\gets b - a$\n\
+         \x20     \\EndIf\n\
+         \x20   \\EndWhile\n\
+         \x20   \\State \\Return $a$\n\
+         \x20 \\EndFunction\n\
+         \\end{algorithmic}\n\\end{algorithm}\n"
+    );
+
+    let mut doc = document!("article");
+    doc.attach(alg.clone().into())?
+        .attach(reference!("alg:euclid"))?;
+    assert!(doc.validate().is_ok());
+    assert!(doc.to_string().contains("\\usepackage[]{algpseudocode}"));
+
+    alg.set_backend(AlgorithmBackend::Algorithm2e);
+    let s = alg.to_string();
+    assert!(s.contains("\\SetKwProg{Fn}{Function}{:}{end}\n\\Fn{\\textsc{Euclid}($a, b$)}{\n"));
+    assert!(s.contains(
+        "\x20   \\uIf{$a > b$}{\n\
+         \x20     $a \\gets a - b$\\;\n\
+         \x20   }\n\
+         \x20   \\Else{\n"
+    ));
+    assert!(s.contains("\x20 \\Return{$a$}\\;\n"));
+
+    let mut doc = document!("article");
+    doc.attach(alg.clone().into())?;
+    assert!(doc.validate().is_ok());
+    alg.set_backend(AlgorithmBackend::Algpseudocode);
+    doc.attach(alg.into())?;
+    // Same label twice, and two backends.
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::LabelDuplicate(_), TexError::AlgorithmBackends]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn overlays() -> Null {
+    for spec in ["2-4,6", "+-", "-3", ".(1)-", "+(-1)-+(2)"] {
+        assert_eq!(Overlay::parse(spec)?.to_string(), format!("<{}>", spec));
+    }
+    assert_eq!(Overlay::parse("<2->")?, Overlay::from(2)?);
+    assert_eq!(Overlay::parse("+-")?, Overlay::incremental());
+    for spec in ["0", "3-2", "", "a", "-"] {
+        assert!(Overlay::parse(spec).is_err());
+    }
+
+    let uncover = Overlaid::new(
+        OverlayCommand::Uncover,
+        Overlay::from(2)?,
+        textchunk!("later", "normal"),
+    );
+    assert_eq!(uncover.to_string(), "\\uncover<2->{later }\n");
+
+    let mut block = Block::new("Proof");
+    block.set_overlay(Overlay::between(3, 4)?);
+    assert!(block.to_string().starts_with("\\begin{block}<3-4>{Proof}"));
+
+    let mut list = List::new(ListType::Itemize);
+    list.set_overlay(Overlay::incremental());
+    list.attach(textchunk!("one", "normal"))?;
+    list.attach_overlaid(textchunk!("two", "normal"), Overlay::only(1)?);
+    let s = list.to_string();
+    assert!(s.starts_with("\\begin{itemize}[<+->]"));
+    assert!(s.contains("\\item one"));
+    assert!(s.contains("\\item<1> two"));
+
+    let mut frame = Frame::new("Steps");
+    frame
+        .attach(list.into())?
+        .attach(pause())?
+        .attach(uncover.clone().into())?;
+    let mut doc = document!("beamer");
+    doc.attach(frame.into())?;
+    assert!(doc.validate().is_ok());
+    assert!(doc.to_string().contains("\\pause\n\\uncover<2->{later }\n"));
+
+    doc.attach(uncover.into())?;
+    assert!(matches!(
+        &doc.problems()[..],
+        [TexError::OverlayOutsideFrame(1)]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn presentation() -> Null {
+    let mut doc = document!("beamer");
+    doc.presentation_mut()
+        .set_theme("metropolis", &["progressbar=frametitle"])
+        .set_color_theme("beaver", &[])
+        .set_font_theme("serif", &[])
+        .hide_navigation_symbols()
+        .number_frames()
+        .set_predefined_template("footline", "page number")
+        .set_aspect_ratio(AspectRatio::SixteenNine)
+        .set_handout(true);
+    let s = doc.to_string();
+    assert!(s.starts_with("\\documentclass[aspectratio=169, handout, ]{beamer}"));
+    assert!(s.contains(
+        "\\usetheme[progressbar=frametitle]{metropolis}\n\
+         \\usecolortheme{beaver}\n\
+         \\usefonttheme{serif}\n\
+         \\setbeamertemplate{navigation symbols}{}\n\
+         \\setbeamertemplate{footline}[page number]\n"
+    ));
+    assert!(s.find("\\usetheme").unwrap() < s.find("\\begin{document}").unwrap());
+
+    // Not a presentation, so none of it applies.
+    let mut doc = document!("article");
+    doc.presentation_mut()
+        .set_theme("metropolis", &[])
+        .set_aspect_ratio(AspectRatio::SixteenNine);
+    let s = doc.to_string();
+    assert!(s.starts_with("\\documentclass[]{article}"));
+    assert!(!s.contains("\\usetheme"));
+
+    Ok(())
+}
+
+#[test]
+fn columns() -> Null {
+    let mut text = Column::new(0.6);
+    text.attach(textchunk!("On the left", "normal"))?;
+    let mut plot = Column::new(0.4);
+    plot.set_alignment(ColumnAlignment::Bottom)
+        .attach(Image::new("plot.png").into())?;
+
+    let mut cols = Columns::new();
+    cols.set_alignment(ColumnAlignment::Top)
+        .add_column(text)?
+        .add_column(plot)?;
+    assert!((cols.total_width() - 1.0).abs() < 1e-9);
+    // Already full.
+    assert!(cols.add_column(Column::new(0.1)).is_err());
+    assert!(Columns::new().add_column(Column::new(0.0)).is_err());
+    assert!(Columns::new().add_column(Column::new(f64::NAN)).is_err());
+    assert!(Columns::new()
+        .add_column(Column::new(f64::INFINITY))
+        .is_err());
+
+    let mut thirds = Columns::new();
+    for _ in 0..3 {
+        thirds.add_column(Column::new(1.0 / 3.0))?;
+    }
+
+    let mut frame = Frame::new("Results");
+    frame.attach(cols.into())?;
+    let mut doc = document!("beamer");
+    doc.attach(frame.into())?;
+    let s = doc.to_string();
+    assert!(s.contains("\\begin{columns}[t] \n \\begin{column}{0.6\\textwidth} \n On the left "));
+    assert!(s.contains("\\begin{column}[b]{0.4\\textwidth} \n \\includegraphics"));
+    assert_eq!(doc.images().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn frame_options() -> Null {
+    let mut frame = Frame::new("Results");
+    frame
+        .set_subtitle("so far")
+        .add_frame_option(FrameOption::Plain)
+        .add_frame_option(FrameOption::Shrink(None))
+        .add_frame_option(FrameOption::Shrink(Some(10)))
+        .add_frame_option(FrameOption::Label("results".to_string()));
+    assert_eq!(frame.frame_options().len(), 3);
+    assert!(frame
+        .to_string()
+        .starts_with("\\begin{frame}[plain, shrink=10, label=results]{Results}{so far} \n "));
+    assert!(Frame::new("Plain")
+        .to_string()
+        .starts_with("\\begin{frame}{Plain} \n "));
+
+    let mut code = Frame::new("Code");
+    code.attach(textchunk!("x = 1", "verbatim"))?;
+    assert!(code.needs_fragile());
+    assert!(code
+        .to_string()
+        .starts_with("\\begin{frame}[fragile]{Code}"));
+    // Not twice.
+    code.add_frame_option(FrameOption::Fragile)
+        .add_frame_option(FrameOption::AllowFrameBreaks);
+    assert!(code
+        .to_string()
+        .starts_with("\\begin{frame}[fragile, allowframebreaks]{Code}"));
+
+    let mut block = Block::new("Listing");
+    block.attach(CodeListing::new("fn main() {}").into())?;
+    let mut nested = Frame::new("Nested");
+    nested.attach(block.into())?;
+    assert!(nested
+        .to_string()
+        .starts_with("\\begin{frame}[fragile]{Nested}"));
+
+    for name in ["verbatim", "lstlisting", "minted"] {
+        let mut frame = Frame::new("Raw");
+        frame.attach(Environment::new(name).into())?;
+        assert!(frame.needs_fragile());
+    }
+    let mut frame = Frame::new("Quote");
+    frame.attach(Environment::new("quote").into())?;
+    assert!(!frame.needs_fragile());
+
+    Ok(())
+}
+
+#[test]
+fn beamer_blocks_and_notes() -> Null {
+    let mut warning = Block::alert("Careful");
+    warning.attach(textchunk!("Division by zero", "normal"))?;
+    assert!(warning
+        .to_string()
+        .starts_with("\\begin{alertblock}{Careful}"));
+    assert!(warning.to_string().ends_with("\\end{alertblock} \n "));
+    let mut example = Block::new("For instance");
+    example.set_kind(BlockKind::Example);
+    assert_eq!(example.kind(), BlockKind::Example);
+    assert!(example.to_string().starts_with("\\begin{exampleblock}"));
+
+    let mut doc = document!("beamer");
+    // Beamer has these already.
+    let mut thm = theorem!(doc, "theorem");
+    thm.attach(textchunk!("There are infinitely many primes.", "normal"))?;
+    let mut frame = Frame::new("Primes");
+    frame
+        .attach(thm.into())?
+        .attach(warning.into())?
+        .add_note("Mention Euclid.");
+    doc.attach(frame.into())?;
+    doc.presentation_mut().set_notes(NotesMode::SecondScreen);
+    assert!(doc.validate().is_ok());
+    let s = doc.to_string();
+    assert!(s.contains("\\begin{theorem}\n"));
+    assert!(!s.contains("\\newtheorem"));
+    assert!(s.contains("\\note{Mention Euclid.} \n  \\end{frame}"));
+    assert!(s.contains("\\usepackage[]{pgfpages}"));
+    assert!(s.contains("\\setbeameroption{show notes on second screen}\n"));
+
+    doc.presentation_mut().set_notes(NotesMode::Hide);
+    let s = doc.to_string();
+    assert!(!s.contains("\\setbeameroption"));
+    assert!(!s.contains("pgfpages"));
+
+    // Sharing beamer's counter, and replacing one of its kinds.
+    let mut claim = TheoremKind::new("claim", "Claim");
+    claim.set_counter(TheoremCounter::SharedWith("theorem".to_string()));
+    doc.new_theorem(claim.clone())?;
+    doc.new_theorem(TheoremKind::new("example", "Beispiel"))?;
+    assert_eq!(doc.get_theorem("example")?.title, "Beispiel");
+    let s = doc.to_string();
+    assert!(s.contains("\\newtheorem{claim}[theorem]{Claim}\n"));
+    assert!(s.contains(
+        "\\let\\example\\relax\\let\\endexample\\relax\n\\newtheorem{example}{Beispiel}\n"
+    ));
+
+    // Not outside of beamer.
+    let mut doc = document!("article");
+    assert!(doc.get_theorem("theorem").is_err());
+    assert!(matches!(
+        doc.new_theorem(claim),
+        Err(TexError::TheoremUndefined(x)) if x == "theorem"
+    ));
+
+    Ok(())
+}
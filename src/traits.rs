@@ -1,6 +1,18 @@
+use std::io::{self, Write};
+
 use crate::prelude::*;
 pub trait AsLatex {
-    fn to_string(&self) -> String;
+    /// Streams the latex straight into `w`, be it a file, a buffer or a socket.
+    /// Nothing is concatenated along the way, so this is the one to use for large documents.
+    fn write_to(&self, w: &mut dyn Write) -> io::Result<()>;
+
+    /// Thin wrapper around `write_to`, for when you want the whole thing in memory.
+    fn to_string(&self) -> String {
+        let mut buf = vec![];
+        self.write_to(&mut buf)
+            .expect("Writing to a Vec<u8> can't fail.");
+        String::from_utf8(buf).expect("Latex output is always valid UTF-8.")
+    }
 }
 
 pub trait Populate {
@@ -12,4 +24,9 @@ pub trait Populate {
 
 pub trait Opt {
     fn add_option(&mut self, opt: &str);
+
+    /// `key=value`, e.g. `label=\alph*` for an enumerate.
+    fn add_key_value(&mut self, key: &str, value: &str) {
+        self.add_option(&format!("{}={}", key, value));
+    }
 }